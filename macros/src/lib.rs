@@ -0,0 +1,504 @@
+//! Procedural macros for the `running` library. They are re-exported by
+//! `running`, and expand to paths within it, so they are not meant to be
+//! depended on directly.
+
+#![allow(clippy::needless_return)] // explicit returns are the style of this crate
+
+// IMPORTS
+use proc_macro::TokenStream;
+use proc_macro2::{Ident, Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::parse::{Parse, ParseStream, Parser};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::{
+    Data, DeriveInput, Expr, Fields, FnArg, ItemFn, Member, MetaNameValue, Pat, ReturnType, Token,
+    Type,
+};
+
+// STRUCT DECLARATIONS
+
+/// The parameters of the `#[task]` attribute
+#[derive(Default)]
+struct TaskParameters {
+    name: Option<Expr>,
+    retries: Option<Expr>,
+    timeout: Option<Expr>,
+    tags: Option<Expr>,
+    depends_on: Option<Expr>,
+    level: Option<Expr>,
+    format: Option<Expr>,
+}
+
+/// The parameters of the `#[running(...)]` attribute of a struct that derives
+/// `Task`
+#[derive(Default)]
+struct DeriveParameters {
+    run: Option<Ident>,
+    returns: Option<Type>,
+    fallible: bool,
+    name: Option<Expr>,
+    level: Option<Expr>,
+    format: Option<Expr>,
+}
+
+// STRUCT IMPLEMENTATIONS
+
+impl TaskParameters {
+    /// Parses parameters like `retries = 2, tags = ["io"]`
+    fn parse(attribute: TokenStream) -> syn::Result<Self> {
+        let mut parameters = TaskParameters::default();
+        let name_values =
+            Punctuated::<MetaNameValue, Token![,]>::parse_terminated.parse(attribute)?;
+        for name_value in name_values {
+            let key = name_value.path.get_ident().map(|ident| ident.to_string());
+            let slot = match key.as_deref() {
+                Some("name") => &mut parameters.name,
+                Some("retries") => &mut parameters.retries,
+                Some("timeout") => &mut parameters.timeout,
+                Some("tags") => &mut parameters.tags,
+                Some("depends_on") => &mut parameters.depends_on,
+                Some("level") => &mut parameters.level,
+                Some("format") => &mut parameters.format,
+                _ => {
+                    return Err(syn::Error::new(
+                        name_value.path.span(),
+                        "unknown parameter, expected one of `name`, `retries`, `timeout`, `tags`, `depends_on`, `level` or `format`",
+                    ));
+                }
+            };
+            if slot.replace(name_value.value).is_some() {
+                return Err(syn::Error::new(
+                    name_value.path.span(),
+                    "parameter given more than once",
+                ));
+            }
+        }
+        return Ok(parameters);
+    }
+}
+
+impl Parse for DeriveParameters {
+    /// Parses parameters like `run = compress, returns = u64, fallible`
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut parameters = DeriveParameters::default();
+        while !input.is_empty() {
+            let key: Ident = input.parse()?;
+            let duplicate = match key.to_string().as_str() {
+                "fallible" => std::mem::replace(&mut parameters.fallible, true),
+                "run" => {
+                    input.parse::<Token![=]>()?;
+                    parameters.run.replace(input.parse()?).is_some()
+                }
+                "returns" => {
+                    input.parse::<Token![=]>()?;
+                    parameters.returns.replace(input.parse()?).is_some()
+                }
+                "name" => {
+                    input.parse::<Token![=]>()?;
+                    parameters.name.replace(input.parse()?).is_some()
+                }
+                "level" => {
+                    input.parse::<Token![=]>()?;
+                    parameters.level.replace(input.parse()?).is_some()
+                }
+                "format" => {
+                    input.parse::<Token![=]>()?;
+                    parameters.format.replace(input.parse()?).is_some()
+                }
+                _ => {
+                    return Err(syn::Error::new(
+                        key.span(),
+                        "unknown parameter, expected one of `run`, `returns`, `fallible`, `name`, `level` or `format`",
+                    ));
+                }
+            };
+            if duplicate {
+                return Err(syn::Error::new(
+                    key.span(),
+                    "parameter given more than once",
+                ));
+            }
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+        return Ok(parameters);
+    }
+}
+
+// FUNCTIONS
+
+/// Declares a function as a named, logged task. The function is kept as it
+/// is, and a module of the same name is generated next to it, with:
+///
+/// - `task(...)`, which takes the arguments of the function and returns a
+///   `LoggedCallable` that calls it, with the name of the function as the
+///   handle, and the arguments rendered through `represent!`. It can be run
+///   once, unless retries are given. Then it is wrapped in a `Retry`, and its
+///   arguments, which have to implement `Clone`, are cloned for each attempt
+/// - `push(&mut job, ...)`, which pushes that task into a `Job` under its name,
+///   along with its timeout, tags and dependencies
+///
+/// The parameters are all optional Rust expressions:
+///
+/// ```ignore
+/// #[task(
+///     name = "compress",                     // the name in a job, the function name by default
+///     retries = 2,                           // retries after the first attempt
+///     timeout = Duration::from_secs(30),     // fails the task in a job after this long
+///     tags = ["io"],                         // tags of the task in a job
+///     depends_on = ["download"],             // names of the tasks to wait for in a job
+///     level = log::Level::Debug,             // the level that runs are logged at
+///     format = "{handle} took {duration}".parse::<LoggingFormat>().unwrap(),
+/// )]
+/// fn compress(path: String, level: u32) -> Result<(), std::io::Error> { ... }
+/// ```
+#[proc_macro_attribute]
+pub fn task(attribute: TokenStream, item: TokenStream) -> TokenStream {
+    let parameters = match TaskParameters::parse(attribute) {
+        Ok(parameters) => parameters,
+        Err(error) => return error.to_compile_error().into(),
+    };
+    let function = syn::parse_macro_input!(item as ItemFn);
+    return match expand_task(parameters, &function) {
+        Ok(expanded) => expanded.into(),
+        Err(error) => {
+            let error = error.to_compile_error();
+            quote!(#function #error).into()
+        }
+    };
+}
+
+/// Generates the task module of a function
+fn expand_task(parameters: TaskParameters, function: &ItemFn) -> syn::Result<TokenStream2> {
+    let signature = &function.sig;
+    if let Some(asyncness) = signature.asyncness {
+        return Err(syn::Error::new(
+            asyncness.span(),
+            "#[task] cannot be used on async functions",
+        ));
+    }
+    if !signature.generics.params.is_empty() || signature.generics.where_clause.is_some() {
+        return Err(syn::Error::new(
+            signature.generics.span(),
+            "#[task] cannot be used on generic functions",
+        ));
+    }
+
+    let mut argument_names = Vec::new();
+    let mut argument_types = Vec::new();
+    for input in signature.inputs.iter() {
+        let typed = match input {
+            FnArg::Typed(typed) => typed,
+            FnArg::Receiver(receiver) => {
+                return Err(syn::Error::new(
+                    receiver.span(),
+                    "#[task] cannot be used on methods",
+                ));
+            }
+        };
+        match (&*typed.pat, &*typed.ty) {
+            (_, Type::ImplTrait(impl_trait)) => {
+                return Err(syn::Error::new(
+                    impl_trait.span(),
+                    "#[task] cannot be used on functions that take `impl Trait` arguments",
+                ));
+            }
+            (Pat::Ident(pattern), argument_type) => {
+                argument_names.push(pattern.ident.clone());
+                argument_types.push(argument_type.clone());
+            }
+            (pattern, _) => {
+                return Err(syn::Error::new(
+                    pattern.span(),
+                    "#[task] needs every argument to be a plain name",
+                ));
+            }
+        }
+    }
+    let return_type = match &signature.output {
+        ReturnType::Default => quote!(()),
+        ReturnType::Type(_, return_type) => {
+            match &**return_type {
+                Type::ImplTrait(impl_trait) => {
+                    return Err(syn::Error::new(
+                        impl_trait.span(),
+                        "#[task] cannot be used on functions that return `impl Trait`",
+                    ));
+                }
+                return_type => quote!(#return_type),
+            }
+        }
+    };
+
+    // The local variables of the generated code cannot clash with the names of
+    // the arguments
+    let job = Ident::new("job", Span::mixed_site());
+    let task_handle = Ident::new("task_handle", Span::mixed_site());
+    let logged_callable = Ident::new("logged_callable", Span::mixed_site());
+    let arguments_string = Ident::new("arguments_string", Span::mixed_site());
+
+    let visibility = &function.vis;
+    let function_name = &signature.ident;
+    let handle = function_name.to_string();
+    let name = match parameters.name {
+        Some(name) => quote!(#name),
+        None => quote!(#handle),
+    };
+    let module_doc = format!("The task of [`{}()`], generated by `#[task]`", handle);
+    let argument_strings = argument_names.iter().map(|argument_name| {
+        let argument_string = argument_name.to_string();
+        let argument_string = argument_string
+            .strip_prefix("r#")
+            .unwrap_or(&argument_string);
+        return format!("{} = {{}}", argument_string);
+    });
+    let arguments_format = argument_strings.collect::<Vec<_>>().join(", ");
+    let level = parameters.level.map(|level| quote!(.level(#level)));
+    let format = parameters
+        .format
+        .map(|format| quote!(.logging_format(#format)));
+    let handle_type = quote!(fn(#(#argument_types),*) -> #return_type);
+    // Only retried tasks keep their handle and clone their arguments, so
+    // that they can be run again
+    let (callable_handle_type, constructor) = match parameters.retries {
+        Some(_) => (handle_type.clone(), quote!(new)),
+        None => {
+            (
+                quote!(::running::callable::OnceHandle<#handle_type>),
+                quote!(once),
+            )
+        }
+    };
+    let logged_callable_type = quote! {
+        ::running::callable::LoggedCallable<(#(#argument_types,)*), #return_type, #callable_handle_type>
+    };
+    let (task_type, task_value) = match parameters.retries {
+        Some(retries) => {
+            (
+                quote!(::running::retry::Retry<#logged_callable_type>),
+                quote!(::running::retry::Retry::new(#logged_callable).max_attempts(1 + #retries)),
+            )
+        }
+        None => (logged_callable_type, quote!(#logged_callable)),
+    };
+    let depends_on = parameters.depends_on.map(|depends_on| {
+        quote! {
+            for dependency in #depends_on {
+                #job.depends_on(&#task_handle, dependency);
+            }
+        }
+    });
+    let tags = parameters.tags.map(|tags| {
+        quote! {
+            for tag in #tags {
+                #job.tag(&#task_handle, tag);
+            }
+        }
+    });
+    let timeout = parameters
+        .timeout
+        .map(|timeout| quote!(#job.timeout(&#task_handle, #timeout);));
+    let module_name = format_ident!("{}", function_name);
+
+    return Ok(quote! {
+        #function
+
+        #[doc = #module_doc]
+        #[allow(dead_code)]
+        #visibility mod #module_name {
+            #[allow(unused_imports)]
+            use super::*;
+
+            /// Makes a logged callable that calls the function with the given
+            /// arguments
+            pub fn task(#(#argument_names: #argument_types),*) -> #task_type {
+                let #arguments_string = ::std::format!(
+                    #arguments_format,
+                    #(::running::represent!(#argument_names)),*
+                );
+                let #logged_callable = ::running::callable::LoggedCallable::#constructor(
+                    super::#function_name as #handle_type,
+                    #handle,
+                )
+                .args((#(#argument_names,)*), #arguments_string)
+                #level
+                #format;
+                return #task_value;
+            }
+
+            /// Pushes the task into a job under its name, along with its
+            /// timeout, tags and dependencies
+            pub fn push(
+                #job: &mut ::running::runnable::Job,
+                #(#argument_names: #argument_types),*
+            ) -> ::running::runnable::TaskHandle<#return_type> {
+                let #task_handle = #job.push_named(#name, task(#(#argument_names),*));
+                #depends_on
+                #tags
+                #timeout
+                return #task_handle;
+            }
+        }
+    });
+}
+
+/// Implements `Run`, `RunAndReturn` and `DescribeTask` for a struct with a
+/// method that runs it. Each run is logged like a `LoggedCallable`, with the
+/// name of the struct as the handle, and its fields as the arguments. Fields
+/// marked `#[running(skip)]` are left out of the logs. The struct is set up
+/// with a `#[running(...)]` attribute:
+///
+/// ```ignore
+/// #[derive(Task)]
+/// #[running(
+///     run = compress,           // the method that runs the task, taking `&mut self`
+///     returns = u64,            // what the method returns, `()` by default
+///     fallible,                 // the method returns a `Result` of what it returns
+///     name = "compress",        // the handle and task name, the struct name by default
+///     level = log::Level::Debug,
+///     format = "{handle}({args}) -> {output}".parse::<LoggingFormat>().unwrap(),
+/// )]
+/// struct Compress {
+///     path: String,
+///     #[running(skip)]
+///     buffer: Vec<u8>,
+/// }
+/// ```
+#[proc_macro_derive(Task, attributes(running))]
+pub fn derive_task(item: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(item as DeriveInput);
+    return match expand_derive_task(&input) {
+        Ok(expanded) => expanded.into(),
+        Err(error) => error.to_compile_error().into(),
+    };
+}
+
+/// Generates the trait implementations of a struct that derives `Task`
+fn expand_derive_task(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let mut parameters = DeriveParameters::default();
+    for attribute in input
+        .attrs
+        .iter()
+        .filter(|attribute| attribute.path().is_ident("running"))
+    {
+        let parsed: DeriveParameters = attribute.parse_args()?;
+        parameters = DeriveParameters {
+            run: parsed.run.or(parameters.run),
+            returns: parsed.returns.or(parameters.returns),
+            fallible: parsed.fallible || parameters.fallible,
+            name: parsed.name.or(parameters.name),
+            level: parsed.level.or(parameters.level),
+            format: parsed.format.or(parameters.format),
+        };
+    }
+    let struct_name = &input.ident;
+    let run = parameters.run.ok_or_else(|| {
+        syn::Error::new(
+            struct_name.span(),
+            "#[derive(Task)] needs the method that runs the task, as in `#[running(run = method)]`",
+        )
+    })?;
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => {
+            return Err(syn::Error::new(
+                struct_name.span(),
+                "#[derive(Task)] can only be used on structs",
+            ));
+        }
+    };
+
+    let mut rendered_fields = Vec::new();
+    for (index, field) in fields.iter().enumerate() {
+        let mut skip = false;
+        for attribute in field
+            .attrs
+            .iter()
+            .filter(|attribute| attribute.path().is_ident("running"))
+        {
+            attribute.parse_nested_meta(|meta| {
+                if meta.path.is_ident("skip") {
+                    skip = true;
+                    return Ok(());
+                }
+                return Err(meta.error("unknown field parameter, expected `skip`"));
+            })?;
+        }
+        if skip {
+            continue;
+        }
+        let (member, label) = match &field.ident {
+            Some(ident) => {
+                let label = ident.to_string();
+                let label = label.strip_prefix("r#").unwrap_or(&label).to_owned();
+                (Member::Named(ident.clone()), label)
+            }
+            None => (Member::Unnamed(index.into()), index.to_string()),
+        };
+        let format = match fields {
+            Fields::Named(_) => format!("{} = {{}}", label),
+            _ => String::from("{}"),
+        };
+        rendered_fields.push(quote!(
+            ::std::format!(#format, ::running::represent!(self.#member))
+        ));
+    }
+
+    let name = match parameters.name {
+        Some(name) => quote!(::std::string::ToString::to_string(&#name)),
+        None => {
+            let name = struct_name.to_string();
+            quote!(::std::string::String::from(#name))
+        }
+    };
+    let return_type = match parameters.returns {
+        Some(return_type) => quote!(#return_type),
+        None => quote!(()),
+    };
+    let level = parameters.level.map(|level| quote!(.level(#level)));
+    let format = parameters
+        .format
+        .map(|format| quote!(.logging_format(#format)));
+    let fallible = match parameters.fallible {
+        true => Some(quote!(.fallible())),
+        false => None,
+    };
+    let logged_callable = Ident::new("logged_callable", Span::mixed_site());
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+
+    return Ok(quote! {
+        impl #impl_generics ::running::DescribeTask for #struct_name #type_generics #where_clause {
+            fn task_name(&self) -> ::std::string::String {
+                return #name;
+            }
+
+            fn task_arguments(&self) -> ::std::string::String {
+                let fields: ::std::vec::Vec<::std::string::String> = ::std::vec![#(#rendered_fields),*];
+                return fields.join(", ");
+            }
+        }
+
+        impl #impl_generics ::running::RunAndReturn for #struct_name #type_generics #where_clause {
+            type ReturnType = #return_type;
+
+            fn run_and_return(&mut self) -> ::std::result::Result<Self::ReturnType, ::running::Error> {
+                let name = ::running::DescribeTask::task_name(self);
+                let arguments = ::running::DescribeTask::task_arguments(self);
+                let mut #logged_callable = ::running::callable::LoggedCallable::once(move || Self::#run(self), name)
+                    .args((), arguments)
+                    #level
+                    #format
+                    #fallible;
+                return ::running::RunAndReturn::run_and_return(&mut #logged_callable);
+            }
+        }
+
+        impl #impl_generics ::running::Run for #struct_name #type_generics #where_clause {
+            fn run(&mut self) -> ::std::result::Result<(), ::running::Error> {
+                return ::running::RunAndReturn::run_and_return(self).map(|_output| ());
+            }
+        }
+    });
+}
@@ -2,13 +2,25 @@
 
 // region: IMPORTS
 
-use crate::Error;
-use crate::Represent;
-use crate::{Run, RunAndCallback, RunAndDebug, RunAndDisplay, RunAndReturn};
-use snafu::{Backtrace, OptionExt, Snafu};
+use crate::runnable::TaskContext;
+#[cfg(feature = "tracing_support")]
+use crate::trace::{self, TracedRun};
+use crate::{AcceptInput, Run, RunAndCallback, RunAndDebug, RunAndDisplay, RunAndReturn};
+use crate::{Error, RunningError};
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local};
+#[cfg(feature = "serde_support")]
+use serde::{Deserialize, Serialize};
+use snafu::{Backtrace, OptionExt, ResultExt, Snafu};
 use std::any::Any;
-use std::fmt::{Debug, Display};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt::{self, Debug, Display, Write};
 use std::ops::{Deref, DerefMut};
+use std::str::FromStr;
+use std::sync::{Arc, Once, RwLock};
+use std::thread;
+use std::time::{Duration, Instant};
 use std::{panic, panic::AssertUnwindSafe};
 
 // endregion: IMPORTS
@@ -22,8 +34,23 @@ pub enum CallableError {
     CallableHandleMissing { backtrace: Backtrace },
     #[snafu(display("Callable arguments missing. Either they were not provided, or were moved during a previous method call"))]
     CallableArgumentsMissing { backtrace: Backtrace },
-    #[snafu(display("Callable panicked"))]
-    CallablePanicked { backtrace: Backtrace },
+    #[snafu(display(
+        "Callable panicked in thread `{}` at {}: {}",
+        thread.as_deref().unwrap_or("<unnamed>"),
+        location.as_deref().unwrap_or("<unknown location>"),
+        message
+    ))]
+    CallablePanicked {
+        message: String,
+        location: Option<String>,
+        thread: Option<String>,
+        backtrace: Backtrace,
+    },
+    #[snafu(display("Callable failed: {}", source))]
+    CallableFailed {
+        source: Box<dyn std::error::Error + Send + Sync>,
+        backtrace: Backtrace,
+    },
     #[snafu(display("Callable handle string missing. It is necessary for logging"))]
     CallableHandleStringMissing { backtrace: Backtrace },
     #[snafu(display("Callable argument string missing. It is necessary for logging"))]
@@ -32,7 +59,65 @@ pub enum CallableError {
 
 impl From<CallableError> for Error {
     fn from(callable_error: CallableError) -> Self {
-        Box::new(callable_error)
+        RunningError::Callable(callable_error)
+    }
+}
+
+#[derive(Debug, Snafu)]
+pub enum LoggingFormatError {
+    #[snafu(display(
+        "Unknown placeholder `{}` at position {} of the logging format",
+        name,
+        position
+    ))]
+    UnknownPlaceholder {
+        name: String,
+        position: usize,
+        backtrace: Backtrace,
+    },
+    #[snafu(display(
+        "Invalid specifier `{}` at position {} of the logging format",
+        specifier,
+        position
+    ))]
+    InvalidSpecifier {
+        specifier: String,
+        position: usize,
+        backtrace: Backtrace,
+    },
+    #[snafu(display(
+        "Invalid timestamp format `{}` at position {} of the logging format",
+        format,
+        position
+    ))]
+    InvalidTimestampFormat {
+        format: String,
+        position: usize,
+        backtrace: Backtrace,
+    },
+    #[snafu(display("No logging format is registered under the name `{}`", name))]
+    UnknownLoggingFormat { name: String, backtrace: Backtrace },
+    #[snafu(display(
+        "Placeholder at position {} of the logging format is never closed",
+        position
+    ))]
+    UnclosedPlaceholder {
+        position: usize,
+        backtrace: Backtrace,
+    },
+    #[snafu(display(
+        "Unmatched `}}` at position {} of the logging format. Use `}}}}` for a literal brace",
+        position
+    ))]
+    UnmatchedBrace {
+        position: usize,
+        backtrace: Backtrace,
+    },
+}
+
+impl From<LoggingFormatError> for Error {
+    fn from(logging_format_error: LoggingFormatError) -> Self {
+        RunningError::LoggingFormat(logging_format_error)
     }
 }
 
@@ -40,30 +125,161 @@ impl From<CallableError> for Error {
 
 // region: CALLABLE
 
+/// Ties the arguments and the return type of a callable to its handle, and
+/// calls the handle. It is implemented for closures, functions and methods of
+/// up to twelve arguments that can be called more than once, whose arguments
+/// are cloned for each call, and for [OnceHandle]s of closures that can only be
+/// called once, whose arguments are moved into the call
+pub trait Handle<A> {
+    type Output;
+
+    /// The arguments that a callable starts with, which are only known for
+    /// handles that take no arguments
+    fn default_arguments() -> Option<A>;
+
+    /// Calls the handle with the stored arguments, keeping the handle, and the
+    /// arguments, for the next call if it can be called again
+    fn call_handle(
+        handle: &mut Option<Self>,
+        arguments: &mut Option<A>,
+    ) -> Result<Self::Output, CallableError>
+    where
+        Self: Sized;
+}
+
+/// The handle of a callable that can only be run once, like a closure that
+/// moves what it captured out of itself. Made by [Callable::once]
+#[derive(Debug, Clone, Copy)]
+pub struct OnceHandle<F>(F);
+
+/// The handle of a logged callable whose handle returns a `Result`, which fails
+/// with [CallableError::CallableFailed] when the handle returns an `Err`. Made
+/// by [LoggedCallable::fallible]
+#[derive(Debug, Clone, Copy)]
+pub struct FallibleHandle<F>(F);
+
+impl<A, T, E, F> Handle<A> for FallibleHandle<F>
+where
+    F: Handle<A, Output = Result<T, E>>,
+    E: std::error::Error + Send + Sync + 'static,
+{
+    type Output = T;
+
+    fn default_arguments() -> Option<A> {
+        return F::default_arguments();
+    }
+
+    fn call_handle(
+        handle: &mut Option<Self>,
+        arguments: &mut Option<A>,
+    ) -> Result<Self::Output, CallableError> {
+        let mut inner_handle = handle
+            .take()
+            .map(|FallibleHandle(inner_handle)| inner_handle);
+        let result = F::call_handle(&mut inner_handle, arguments);
+        *handle = inner_handle.map(FallibleHandle);
+        return result?
+            .map_err(|inner| Box::new(inner) as Box<dyn std::error::Error + Send + Sync>)
+            .context(CallableFailed);
+    }
+}
+
+/// Implements [Handle] for handles of the given argument types, and for
+/// [OnceHandle]s of them
+macro_rules! impl_handle {
+    ($default_arguments:expr; $($argument:ident),*) => {
+        #[allow(non_snake_case)]
+        impl<R, F, $($argument: Clone),*> Handle<($($argument,)*)> for F
+        where
+            F: FnMut($($argument),*) -> R,
+        {
+            type Output = R;
+
+            fn default_arguments() -> Option<($($argument,)*)> {
+                return $default_arguments;
+            }
+
+            fn call_handle(
+                handle: &mut Option<Self>,
+                arguments: &mut Option<($($argument,)*)>,
+            ) -> Result<Self::Output, CallableError> {
+                let ($($argument,)*) = arguments.clone().context(CallableArgumentsMissing)?;
+                let handle: &mut F = handle.as_mut().context(CallableHandleMissing)?;
+                return Ok(handle($($argument),*));
+            }
+        }
+
+        #[allow(non_snake_case)]
+        impl<R, F, $($argument),*> Handle<($($argument,)*)> for OnceHandle<F>
+        where
+            F: FnOnce($($argument),*) -> R,
+        {
+            type Output = R;
+
+            fn default_arguments() -> Option<($($argument,)*)> {
+                return $default_arguments;
+            }
+
+            fn call_handle(
+                handle: &mut Option<Self>,
+                arguments: &mut Option<($($argument,)*)>,
+            ) -> Result<Self::Output, CallableError> {
+                let ($($argument,)*) = arguments.take().context(CallableArgumentsMissing)?;
+                let OnceHandle(handle) = handle.take().context(CallableHandleMissing)?;
+                return Ok(handle($($argument),*));
+            }
+        }
+    };
+}
+
+impl_handle!(Some(()););
+impl_handle!(None; A1);
+impl_handle!(None; A1, A2);
+impl_handle!(None; A1, A2, A3);
+impl_handle!(None; A1, A2, A3, A4);
+impl_handle!(None; A1, A2, A3, A4, A5);
+impl_handle!(None; A1, A2, A3, A4, A5, A6);
+impl_handle!(None; A1, A2, A3, A4, A5, A6, A7);
+impl_handle!(None; A1, A2, A3, A4, A5, A6, A7, A8);
+impl_handle!(None; A1, A2, A3, A4, A5, A6, A7, A8, A9);
+impl_handle!(None; A1, A2, A3, A4, A5, A6, A7, A8, A9, A10);
+impl_handle!(None; A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11);
+impl_handle!(None; A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12);
+
 /// Stores the minimum information needed define a callable
 #[derive(Debug, Clone, Copy)]
-#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
 pub struct AtomicCallable<
     A, // arguments as a tuple
     R, // return type
-    F, // Fn trait (like Fn, FnOnce, and FnMut)
+    F, // handle (a function, a closure, or a OnceHandle of a closure)
 > where
-    F: FnOnce<A, Output = R>,
+    F: Handle<A, Output = R>,
 {
     handle: Option<F>,    // the callable's handle
     arguments: Option<A>, // a tuple representing the arguments
 }
 
-/// A struct denoting a callable object, like a function, method, or a closure
-/// that implements one of Fn, FnOnce or FnMut.
+/// A struct denoting a callable object, like a function, method, or a closure.
+/// Callables made with [Callable::new] keep their handle and clone their
+/// arguments for each run, so they can be run again. Callables made with
+/// [Callable::once] take closures that can only be called once, and move their
+/// arguments into the run
+///
+/// A panic of a run is caught and returned as
+/// [CallableError::CallablePanicked]. To tell where it happened, the first run
+/// in the process installs a global panic hook with [std::panic::set_hook],
+/// which stays installed. While a callable runs on a thread, the hook records
+/// the location of its panics instead of printing them. Every other panic is
+/// passed on to the hook that was installed before, so it is printed as
+/// usual. A hook that is set later replaces this one, and the locations of
+/// the panics are then unknown
 #[derive(Debug, Clone)]
-#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
 pub struct Callable<
     A, // arguments as a tuple
     R, // return type
-    F, // Fn trait (like Fn, FnOnce, and FnMut)
+    F, // handle (a function, a closure, or a OnceHandle of a closure)
 > where
-    F: FnOnce<A, Output = R>,
+    F: Handle<A, Output = R>,
 {
     atomic_callable: AtomicCallable<A, R, F>,
 }
@@ -74,7 +290,7 @@ pub type Closure<A, R, F> = Callable<A, R, F>;
 
 impl<A, R, F> Deref for Callable<A, R, F>
 where
-    F: FnOnce<A, Output = R>,
+    F: Handle<A, Output = R>,
 {
     type Target = AtomicCallable<A, R, F>;
 
@@ -85,44 +301,24 @@ where
 
 impl<A, R, F> DerefMut for Callable<A, R, F>
 where
-    F: FnOnce<A, Output = R>,
+    F: Handle<A, Output = R>,
 {
     fn deref_mut(&mut self) -> &mut Self::Target {
         return &mut self.atomic_callable;
     }
 }
 
-trait OptionalNullArgument<A> {
-    fn optional_null_argument(&self) -> Option<A>;
-}
-
-impl<A, R, F> OptionalNullArgument<A> for F
-where 
-    F: FnOnce<A, Output = R>,
-{
-    default fn optional_null_argument(&self) -> Option<A> {
-        return None;
-    }
-}
-
-impl<R, F> OptionalNullArgument<()> for F
-where 
-    F: FnOnce<(), Output = R>,
-{
-    fn optional_null_argument(&self) -> Option<()> {
-        return Some(());
-    }
-}
-
 impl<A, R, F> Callable<A, R, F>
 where
-    F: FnOnce<A, Output = R>,
+    F: Handle<A, Output = R>,
 {
-    /// Creates a new callable with the given handle and no arguments
+    /// Creates a new callable with the given handle and no arguments. The
+    /// handle is kept after each run, and the arguments are cloned for it, so
+    /// that the callable can be run again
     pub fn new(handle: F) -> Self {
         return Callable {
             atomic_callable: AtomicCallable {
-                arguments: handle.optional_null_argument(),
+                arguments: F::default_arguments(),
                 handle: Some(handle),
             },
         };
@@ -130,140 +326,200 @@ where
 
     /// Stores arguments in the callable
     pub fn args(mut self, arguments: A) -> Self {
-        self.arguments = Some(arguments);
+        self.atomic_callable.arguments = Some(arguments);
         return self;
     }
 
-    /// Flattens the result of a callable call, and combines the errors generated by the callable panicking, and other errors of missing data
+    /// Replaces the arguments of the callable, for example between two runs
+    pub fn set_args(&mut self, arguments: A) {
+        self.atomic_callable.arguments = Some(arguments);
+    }
+
+    /// Makes a callable whose handle returns a `Result` fail when the handle
+    /// returns an `Err`
+    pub fn fallible(self) -> FallibleCallable<Self> {
+        return FallibleCallable::new(self);
+    }
+
+    /// Calls the handle with the arguments, and turns a panic into an error
+    fn call(&mut self) -> Result<R, Error> {
+        let atomic_callable = &mut self.atomic_callable;
+        let call_result = catch_panic(|| {
+            F::call_handle(&mut atomic_callable.handle, &mut atomic_callable.arguments)
+        });
+        return Callable::<A, R, F>::compose_run_result(call_result);
+    }
+
+    /// Flattens the result of a callable call, and combines the errors
+    /// generated by the callable panicking, and other errors of missing data
     fn compose_run_result(
-        call_result: Result<Result<R, CallableError>, Box<dyn Any + Send>>,
+        call_result: Result<Result<R, CallableError>, PanicDetails>,
     ) -> Result<R, Error> {
         let result = match call_result {
             Ok(inner) => inner,
-            Err(_inner) => CallablePanicked.fail().into(),
+            Err(PanicDetails {
+                message,
+                location,
+                thread,
+            }) => {
+                CallablePanicked {
+                    message,
+                    location,
+                    thread,
+                }
+                .fail()
+            }
         };
         let result = result.map_err(|error: CallableError| -> Error { error.into() });
         return result;
     }
 }
 
-trait InnerRunOnce<A, R, F>
+impl<A, R, G> Callable<A, R, OnceHandle<G>>
 where
-    F: FnOnce<A, Output = R>,
+    OnceHandle<G>: Handle<A, Output = R>,
 {
-    fn inner_run_once(&mut self) -> Result<Result<R, CallableError>, Box<dyn Any + Send>>;
+    /// Creates a new callable with a handle that can only be called once, like
+    /// a closure that moves what it captured out of itself, and no arguments.
+    /// The handle and the arguments are moved into the first run, and later
+    /// runs fail
+    pub fn once(handle: G) -> Self {
+        return Callable {
+            atomic_callable: AtomicCallable {
+                arguments: OnceHandle::<G>::default_arguments(),
+                handle: Some(OnceHandle(handle)),
+            },
+        };
+    }
 }
 
-impl<A, R, F> InnerRunOnce<A, R, F> for Callable<A, R, F>
-where
-    F: FnOnce<A, Output = R>,
-{
-    fn inner_run_once(&mut self) -> Result<Result<R, CallableError>, Box<dyn Any + Send>> {
-        return panic::catch_unwind(AssertUnwindSafe(|| -> Result<R, CallableError> {
-            let arguments: A = self.arguments.take().context(CallableArgumentsMissing)?;
-            let handle: F = self.handle.take().context(CallableHandleMissing)?;
-            Ok(handle.call_once(arguments))
-        }));
-    }
+/// What is known about a panic of a callable
+struct PanicDetails {
+    message: String,
+    location: Option<String>,
+    thread: Option<String>,
 }
 
-trait InnerRunMut<A, R, F>
-where
-    F: FnMut<A, Output = R>,
-{
-    fn inner_run_mut(&mut self) -> Result<Result<R, CallableError>, Box<dyn Any + Send>>;
+thread_local! {
+    /// While a callable runs on this thread, holds the location of its panic,
+    /// once the panic hook has recorded it
+    static PANIC_LOCATION: RefCell<Option<Option<String>>> = const { RefCell::new(None) };
 }
 
-impl<A, R, F> InnerRunMut<A, R, F> for Callable<A, R, F>
-where
-    F: FnMut<A, Output = R>,
-{
-    fn inner_run_mut(&mut self) -> Result<Result<R, CallableError>, Box<dyn Any + Send>> {
-        return panic::catch_unwind(AssertUnwindSafe(|| -> Result<R, CallableError> {
-            let arguments: A = self.arguments.take().context(CallableArgumentsMissing)?;
-            let handle: &mut F = self.handle.as_mut().context(CallableHandleMissing)?;
-            Ok(handle.call_mut(arguments))
-        }));
-    }
+static PANIC_HOOK_INSTALLER: Once = Once::new();
+
+/// Installs a panic hook that records the location of the panics of running
+/// callables instead of printing them, as they are turned into errors. Other
+/// panics, including those of threads that do not run a callable, are passed
+/// on to the hook that was installed before. It is installed once, for the
+/// rest of the process, as documented on [Callable]
+fn install_panic_hook() {
+    let previous_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |panic_info| {
+        let recorded = PANIC_LOCATION.with(|panic_location| {
+            match panic_location.try_borrow_mut() {
+                Ok(mut panic_location) => {
+                    match panic_location.as_mut() {
+                        Some(location) => {
+                            *location = panic_info.location().map(|location| location.to_string());
+                            true
+                        }
+                        None => false,
+                    }
+                }
+                Err(_) => false,
+            }
+        });
+        if !recorded {
+            previous_hook(panic_info);
+        }
+    }));
 }
 
-trait InnerRun<A, R, F>
-where
-    F: Fn<A, Output = R>,
-{
-    fn inner_run(&mut self) -> Result<Result<R, CallableError>, Box<dyn Any + Send>>;
+/// The message a panic was started with, when it is a string
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> String {
+    return match payload.downcast_ref::<&str>() {
+        Some(message) => message.to_string(),
+        None => {
+            match payload.downcast_ref::<String>() {
+                Some(message) => message.clone(),
+                None => String::from("<non-string panic payload>"),
+            }
+        }
+    };
 }
 
-impl<A, R, F> InnerRun<A, R, F> for Callable<A, R, F>
-where
-    F: Fn<A, Output = R>,
-{
-    fn inner_run(&mut self) -> Result<Result<R, CallableError>, Box<dyn Any + Send>> {
-        return panic::catch_unwind(AssertUnwindSafe(|| -> Result<R, CallableError> {
-            let arguments: A = self.arguments.take().context(CallableArgumentsMissing)?;
-            let handle: &mut F = self.handle.as_mut().context(CallableHandleMissing)?;
-            Ok(handle.call(arguments))
-        }));
-    }
+/// Does what [panic::catch_unwind] does, but also gives the message, location
+/// and thread of the panic
+fn catch_panic<T, C: FnOnce() -> T>(callback: C) -> Result<T, PanicDetails> {
+    PANIC_HOOK_INSTALLER.call_once(install_panic_hook);
+    let outer_location = PANIC_LOCATION.with(|panic_location| panic_location.replace(Some(None)));
+    let result = panic::catch_unwind(AssertUnwindSafe(callback));
+    let location = PANIC_LOCATION.with(|panic_location| panic_location.replace(outer_location));
+    return result.map_err(|payload| {
+        return PanicDetails {
+            message: panic_message(payload.as_ref()),
+            location: location.flatten(),
+            thread: thread::current().name().map(String::from),
+        };
+    });
 }
 
 impl<A, R, F> RunAndReturn for Callable<A, R, F>
 where
-    F: FnOnce<A, Output = R>,
+    F: Handle<A, Output = R>,
 {
     type ReturnType = R;
 
-    default fn run_and_return(&mut self) -> Result<Self::ReturnType, Error> {
-        return Callable::<A, R, F>::compose_run_result(self.inner_run_once());
+    /// With tracing, the run is traced in a span, named after the type of the
+    /// handle
+    fn run_and_return(&mut self) -> Result<Self::ReturnType, Error> {
+        #[cfg(feature = "tracing_support")]
+        {
+            let traced_run =
+                TracedRun::start(trace::callable_span(None, std::any::type_name::<F>(), None));
+            let result = traced_run.span().in_scope(|| self.call());
+            traced_run.complete_result(&result);
+            return result;
+        }
+        #[cfg(not(feature = "tracing_support"))]
+        return self.call();
     }
 }
 
-impl<A, R, F> RunAndReturn for Callable<A, R, F>
+impl<A, R, F> Run for Callable<A, R, F>
 where
-    F: FnMut<A, Output = R>,
+    F: Handle<A, Output = R>,
 {
-    default fn run_and_return(&mut self) -> Result<Self::ReturnType, Error> {
-        return Callable::<A, R, F>::compose_run_result(self.inner_run_mut());
+    fn run(&mut self) -> Result<(), Error> {
+        return self.run_and_return().map(|_inner| ());
     }
 }
 
-impl<A, R, F> RunAndReturn for Callable<A, R, F>
+impl<A, R, F> RunAndCallback for Callable<A, R, F>
 where
-    F: Fn<A, Output = R>,
+    F: Handle<A, Output = R>,
 {
-    fn run_and_return(&mut self) -> Result<Self::ReturnType, Error> {
-        return Callable::<A, R, F>::compose_run_result(self.inner_run());
+    fn run_and_then<C: FnOnce(Self::ReturnType)>(&mut self, callback: C) -> Result<(), Error> {
+        return self.run_and_return().map(callback);
     }
 }
 
-impl<A, R, F> Run for Callable<A, R, F>
+impl<A, R, F> AcceptInput for Callable<A, R, F>
 where
-    F: FnOnce<A, Output = R>,
+    F: Handle<A, Output = R>,
 {
-    default fn run(&mut self) -> Result<(), Error> {
-        return self.run_and_return().map(|_inner| ());
-    }
-}
+    type Input = A;
 
-impl<A, R, F> RunAndCallback for Callable<A, R, F>
-where
-    F: FnOnce<A, Output = R>,
-{
-    fn run_and_then<C: FnOnce(Self::ReturnType) -> ()>(
-        &mut self,
-        callback: C,
-    ) -> Result<(), Error> {
-        match self.run_and_return() {
-            Ok(inner) => Ok(callback(inner)),
-            Err(inner) => Err(inner),
-        }
+    fn accept_input(&mut self, input: Self::Input) {
+        self.arguments = Some(input);
     }
 }
 
 impl<A, R, F> RunAndDebug for Callable<A, R, F>
 where
     R: Debug,
-    F: FnOnce<A, Output = R>,
+    F: Handle<A, Output = R>,
 {
     fn run_and_debug(&mut self) -> Result<String, Error> {
         match self.run_and_return() {
@@ -276,7 +532,7 @@ where
 impl<A, R, F> RunAndDisplay for Callable<A, R, F>
 where
     R: Display,
-    F: FnOnce<A, Output = R>,
+    F: Handle<A, Output = R>,
 {
     fn run_and_display(&mut self) -> Result<String, Error> {
         match self.run_and_return() {
@@ -290,35 +546,101 @@ where
 
 // region: LOGGING INFO
 
+/// The timestamp format of the `{start_time}` and `{end_time}` placeholders
+/// when none is given, in the syntax of [chrono::format::strftime]
+pub const DEFAULT_TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.3f";
+
 /// The logging data for a callable. Contains the string form of the callable's
 /// handle and the string form of its arguments
 #[derive(Debug, Clone)]
-#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
 struct LoggingData {
     handle: String,
     arguments: String,
 }
 
+/// The values that the tokens of a logging format are rendered from
+struct LogValues<'v> {
+    task_id: usize,
+    handle: &'v str,
+    arguments: &'v str,
+    output: &'v str,
+    start_time: Option<DateTime<Local>>,
+    duration: Option<Duration>,
+    error: Option<&'v (dyn std::error::Error + 'static)>,
+    arguments_type: &'static str,
+    return_type: &'static str,
+}
+
 /// Represents one token within the format specification of a callable. The
-/// format specification may have the callable handle, its arguments, and
-/// arbitrary strings. Use the `new` and `append` methods to build up the format
-#[derive(Debug, Clone)]
+/// format specification may have the callable handle, its arguments, details
+/// of its last run, and arbitrary strings. Use the `new` and `append` methods
+/// to build up the format, or parse it from a template
+#[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
 pub enum LoggingFormatToken {
     Handle,
     Args,
     Output,
+    TaskId,
+    Duration,
+    StartTime(String), // with a strftime format
+    EndTime(String),   // with a strftime format
+    Status,            // `succeeded` or `failed`
+    ErrorChain,        // the error and the sources it does not display, separated by colons
+    Thread,
+    ArgsType,
+    ReturnType,
     ArbitraryString(String),
+    Specified(Box<LoggingFormatToken>, FormatSpec), // a token padded or truncated by a spec
 }
 
-/// The logging format for a callable, in the format of an ordered list. Each
-/// item in the list is a [LoggingFormatToken]
-#[derive(Debug, Clone)]
+/// How a token is aligned within its width
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub enum Alignment {
+    Left,
+    Right,
+    Center,
+}
+
+/// The width and truncation of a token, written like a format specifier of
+/// the standard library after the name of a placeholder, as in
+/// `{handle:>20.10}`
+#[derive(Debug, Clone, Copy, PartialEq)]
 #[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct FormatSpec {
+    pub alignment: Alignment,
+    pub width: Option<usize>,     // the minimum width, in characters
+    pub precision: Option<usize>, // the maximum width, in characters
+}
+
+/// The logging format for a callable, in the format of an ordered list. Each
+/// item in the list is a [LoggingFormatToken]. A format can be parsed from a
+/// template such as `"[{task_id}] {handle}({args}) -> {output} in {duration}"`,
+/// where `{{` and `}}` stand for literal braces
+#[derive(Debug, Clone, PartialEq)]
 pub struct LoggingFormat {
     logging_format: Vec<LoggingFormatToken>,
 }
 
+/// The logging format chosen for a logged callable
+#[derive(Debug, Clone)]
+enum LoggingFormatChoice {
+    Shared(Arc<LoggingFormat>),
+    Named(String), // looked up in the registry when the callable logs
+}
+
+/// The process-wide default logging format, and the formats registered by name
+struct LoggingFormatRegistry {
+    default: Option<Arc<LoggingFormat>>,
+    named: BTreeMap<String, Arc<LoggingFormat>>,
+}
+
+static LOGGING_FORMATS: RwLock<LoggingFormatRegistry> = RwLock::new(LoggingFormatRegistry {
+    default: None,
+    named: BTreeMap::new(),
+});
+
 pub type LoggingFormatBuilder = LoggingFormat;
 
 impl Deref for LoggingFormat {
@@ -366,6 +688,45 @@ impl LoggingFormat {
         self.push(LoggingFormatToken::ArbitraryString(given_string.into()));
         return self;
     }
+
+    /// Append any token to the end of the format specification
+    pub fn append_token(mut self, token: LoggingFormatToken) -> Self {
+        self.push(token);
+        return self;
+    }
+
+    /// Sets the format that logged callables use when neither they nor the
+    /// job running them have one, for the whole process
+    pub fn set_global_default<L: Into<Arc<LoggingFormat>>>(logging_format: L) {
+        LOGGING_FORMATS.write().unwrap().default = Some(logging_format.into());
+    }
+
+    /// The format that logged callables use when neither they nor the job
+    /// running them have one. It is [LoggingFormat::default] unless another
+    /// format was set with [LoggingFormat::set_global_default]
+    pub fn global_default() -> Arc<LoggingFormat> {
+        return LOGGING_FORMATS
+            .read()
+            .unwrap()
+            .default
+            .clone()
+            .unwrap_or_else(|| Arc::new(LoggingFormat::default()));
+    }
+
+    /// Registers a format under a name for the whole process, replacing any
+    /// format that was registered under the same name
+    pub fn register<S: Into<String>, L: Into<Arc<LoggingFormat>>>(name: S, logging_format: L) {
+        LOGGING_FORMATS
+            .write()
+            .unwrap()
+            .named
+            .insert(name.into(), logging_format.into());
+    }
+
+    /// The format registered under the given name, if any
+    pub fn named(name: &str) -> Option<Arc<LoggingFormat>> {
+        return LOGGING_FORMATS.read().unwrap().named.get(name).cloned();
+    }
 }
 
 impl Default for LoggingFormat {
@@ -379,34 +740,372 @@ impl Default for LoggingFormat {
     }
 }
 
+impl LoggingFormatToken {
+    /// The placeholder name of the token in a template, or `None` for
+    /// arbitrary strings
+    fn placeholder_name(&self) -> Option<&'static str> {
+        return match self {
+            LoggingFormatToken::Handle => Some("handle"),
+            LoggingFormatToken::Args => Some("args"),
+            LoggingFormatToken::Output => Some("output"),
+            LoggingFormatToken::TaskId => Some("task_id"),
+            LoggingFormatToken::Duration => Some("duration"),
+            LoggingFormatToken::StartTime(_) => Some("start_time"),
+            LoggingFormatToken::EndTime(_) => Some("end_time"),
+            LoggingFormatToken::Status => Some("status"),
+            LoggingFormatToken::ErrorChain => Some("error_chain"),
+            LoggingFormatToken::Thread => Some("thread"),
+            LoggingFormatToken::ArgsType => Some("args_type"),
+            LoggingFormatToken::ReturnType => Some("return_type"),
+            LoggingFormatToken::ArbitraryString(_) => None,
+            LoggingFormatToken::Specified(token, _) => token.placeholder_name(),
+        };
+    }
+
+    /// The argument of the placeholder of the token in a template, written in
+    /// parentheses after its name, if it has one
+    fn placeholder_argument(&self) -> Option<&str> {
+        return match self {
+            LoggingFormatToken::StartTime(format) | LoggingFormatToken::EndTime(format)
+                if format != DEFAULT_TIMESTAMP_FORMAT =>
+            {
+                Some(format)
+            }
+            LoggingFormatToken::Specified(token, _) => token.placeholder_argument(),
+            _ => None,
+        };
+    }
+
+    /// Parses the inside of a placeholder, like `handle`, `handle:>20.10`, or
+    /// `start_time(%H:%M:%S):>12`
+    fn from_placeholder(placeholder: &str, position: usize) -> Result<Self, LoggingFormatError> {
+        let (name, argument, specifier) = match placeholder.split_once('(') {
+            Some((name, rest)) => {
+                match rest.rsplit_once(')') {
+                    Some((argument, rest)) => {
+                        match rest.strip_prefix(':') {
+                            Some(specifier) => (name, Some(argument), Some(specifier)),
+                            None if rest.is_empty() => (name, Some(argument), None),
+                            None => {
+                                return InvalidSpecifier {
+                                    specifier: rest,
+                                    position,
+                                }
+                                .fail()
+                            }
+                        }
+                    }
+                    None => {
+                        return UnknownPlaceholder {
+                            name: placeholder,
+                            position,
+                        }
+                        .fail()
+                    }
+                }
+            }
+            None => {
+                match placeholder.split_once(':') {
+                    Some((name, specifier)) => (name, None, Some(specifier)),
+                    None => (placeholder, None, None),
+                }
+            }
+        };
+        let timestamp_format = || -> Result<String, LoggingFormatError> {
+            let format = argument.unwrap_or(DEFAULT_TIMESTAMP_FORMAT);
+            if StrftimeItems::new(format).any(|item| item == Item::Error) {
+                return InvalidTimestampFormat { format, position }.fail();
+            }
+            return Ok(format.to_string());
+        };
+        let token = match (name.trim(), argument) {
+            ("handle", None) => LoggingFormatToken::Handle,
+            ("args", None) => LoggingFormatToken::Args,
+            ("output", None) => LoggingFormatToken::Output,
+            ("task_id", None) => LoggingFormatToken::TaskId,
+            ("duration", None) => LoggingFormatToken::Duration,
+            ("start_time", _) => LoggingFormatToken::StartTime(timestamp_format()?),
+            ("end_time", _) => LoggingFormatToken::EndTime(timestamp_format()?),
+            ("status", None) => LoggingFormatToken::Status,
+            ("error_chain", None) => LoggingFormatToken::ErrorChain,
+            ("thread", None) => LoggingFormatToken::Thread,
+            ("args_type", None) => LoggingFormatToken::ArgsType,
+            ("return_type", None) => LoggingFormatToken::ReturnType,
+            _ => {
+                return UnknownPlaceholder {
+                    name: placeholder,
+                    position,
+                }
+                .fail();
+            }
+        };
+        return match specifier {
+            Some(specifier) => {
+                let spec = FormatSpec::parse(specifier).context(InvalidSpecifier {
+                    specifier,
+                    position,
+                })?;
+                Ok(LoggingFormatToken::Specified(Box::new(token), spec))
+            }
+            None => Ok(token),
+        };
+    }
+
+    /// Renders the token from the values of a run
+    fn render(&self, values: &LogValues) -> String {
+        return match self {
+            LoggingFormatToken::Handle => values.handle.to_string(),
+            LoggingFormatToken::Args => values.arguments.to_string(),
+            LoggingFormatToken::Output => values.output.to_string(),
+            LoggingFormatToken::TaskId => values.task_id.to_string(),
+            LoggingFormatToken::Duration => {
+                values
+                    .duration
+                    .map(|duration| format!("{:?}", duration))
+                    .unwrap_or_default()
+            }
+            LoggingFormatToken::StartTime(format) => render_timestamp(values.start_time, format),
+            LoggingFormatToken::EndTime(format) => {
+                render_timestamp(
+                    values
+                        .start_time
+                        .zip(values.duration)
+                        .map(|(start_time, duration)| {
+                            start_time
+                                + chrono::Duration::from_std(duration)
+                                    .unwrap_or_else(|_| chrono::Duration::zero())
+                        }),
+                    format,
+                )
+            }
+            LoggingFormatToken::Status => {
+                match values.error {
+                    Some(_) => String::from("failed"),
+                    None => String::from("succeeded"),
+                }
+            }
+            LoggingFormatToken::ErrorChain => {
+                let mut chain = Vec::new();
+                let mut parent = String::new();
+                let mut error = values.error;
+                while let Some(inner) = error {
+                    // Most errors already display their source after a colon,
+                    // which is then not repeated
+                    let message = inner.to_string();
+                    if parent != message && !parent.ends_with(&format!(": {}", message)) {
+                        chain.push(message.clone());
+                    }
+                    parent = message;
+                    error = inner.source();
+                }
+                chain.join(": ")
+            }
+            LoggingFormatToken::Thread => {
+                thread::current().name().unwrap_or("<unnamed>").to_string()
+            }
+            LoggingFormatToken::ArgsType => values.arguments_type.to_string(),
+            LoggingFormatToken::ReturnType => values.return_type.to_string(),
+            LoggingFormatToken::ArbitraryString(arbitrary_string) => arbitrary_string.clone(),
+            LoggingFormatToken::Specified(token, spec) => spec.apply(token.render(values)),
+        };
+    }
+}
+
+/// Renders a timestamp in the given strftime format, or an empty string if
+/// there is no timestamp or the format is invalid
+fn render_timestamp(timestamp: Option<DateTime<Local>>, format: &str) -> String {
+    let mut rendered = String::new();
+    if let Some(timestamp) = timestamp {
+        if write!(rendered, "{}", timestamp.format(format)).is_err() {
+            rendered.clear();
+        }
+    }
+    return rendered;
+}
+
+impl FormatSpec {
+    /// Parses a specifier like `>20.10`, or returns `None` if it is invalid
+    fn parse(specifier: &str) -> Option<Self> {
+        let (alignment, rest) = match specifier.chars().next() {
+            Some('<') => (Alignment::Left, &specifier[1..]),
+            Some('>') => (Alignment::Right, &specifier[1..]),
+            Some('^') => (Alignment::Center, &specifier[1..]),
+            _ => (Alignment::Left, specifier),
+        };
+        let (width, precision) = match rest.split_once('.') {
+            Some((width, precision)) => (width, Some(precision.parse().ok()?)),
+            None => (rest, None),
+        };
+        let width = match width {
+            "" => None,
+            width => Some(width.parse().ok()?),
+        };
+        return Some(FormatSpec {
+            alignment,
+            width,
+            precision,
+        });
+    }
+
+    /// Truncates the rendered token to the precision, and pads it to the width
+    fn apply(&self, rendered: String) -> String {
+        let truncated: String = match self.precision {
+            Some(precision) => rendered.chars().take(precision).collect(),
+            None => rendered,
+        };
+        let padding = self
+            .width
+            .map_or(0, |width| width.saturating_sub(truncated.chars().count()));
+        let (left, right) = match self.alignment {
+            Alignment::Left => (0, padding),
+            Alignment::Right => (padding, 0),
+            Alignment::Center => (padding / 2, padding - padding / 2),
+        };
+        return format!("{}{}{}", " ".repeat(left), truncated, " ".repeat(right));
+    }
+}
+
+impl FromStr for LoggingFormat {
+    type Err = LoggingFormatError;
+
+    /// Parses a template, in which placeholders like `{handle}` or
+    /// `{output:>10.40}` are replaced by tokens, and `{{` and `}}` are literal
+    /// braces
+    fn from_str(template: &str) -> Result<Self, Self::Err> {
+        let mut logging_format = LoggingFormat::new();
+        let mut literal = String::new();
+        let mut characters = template.char_indices().peekable();
+        while let Some((position, character)) = characters.next() {
+            match character {
+                '{' if characters.next_if(|&(_, next)| next == '{').is_some() => literal.push('{'),
+                '}' if characters.next_if(|&(_, next)| next == '}').is_some() => literal.push('}'),
+                '}' => return UnmatchedBrace { position }.fail(),
+                '{' => {
+                    let mut placeholder = String::new();
+                    loop {
+                        match characters.next() {
+                            Some((_, '}')) => break,
+                            Some((_, inner)) => placeholder.push(inner),
+                            None => return UnclosedPlaceholder { position }.fail(),
+                        }
+                    }
+                    if !literal.is_empty() {
+                        logging_format.push(LoggingFormatToken::ArbitraryString(std::mem::take(
+                            &mut literal,
+                        )));
+                    }
+                    logging_format.push(LoggingFormatToken::from_placeholder(
+                        &placeholder,
+                        position,
+                    )?);
+                }
+                _ => literal.push(character),
+            }
+        }
+        if !literal.is_empty() {
+            logging_format.push(LoggingFormatToken::ArbitraryString(literal));
+        }
+        return Ok(logging_format);
+    }
+}
+
+impl Display for LoggingFormat {
+    /// Writes the format as a template that parses back into the same format
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for token in self.iter() {
+            match (token, token.placeholder_name()) {
+                (LoggingFormatToken::ArbitraryString(arbitrary_string), _) => {
+                    write!(
+                        formatter,
+                        "{}",
+                        arbitrary_string.replace('{', "{{").replace('}', "}}")
+                    )?
+                }
+                (_, Some(name)) => {
+                    write!(formatter, "{{{}", name)?;
+                    if let Some(argument) = token.placeholder_argument() {
+                        write!(formatter, "({})", argument)?;
+                    }
+                    if let LoggingFormatToken::Specified(_, spec) = token {
+                        write!(formatter, ":{}", spec)?;
+                    }
+                    write!(formatter, "}}")?;
+                }
+                (_, None) => {}
+            }
+        }
+        return Ok(());
+    }
+}
+
+impl Display for FormatSpec {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.alignment {
+            Alignment::Left => {}
+            Alignment::Right => write!(formatter, ">")?,
+            Alignment::Center => write!(formatter, "^")?,
+        }
+        if let Some(width) = self.width {
+            write!(formatter, "{}", width)?;
+        }
+        if let Some(precision) = self.precision {
+            write!(formatter, ".{}", precision)?;
+        }
+        return Ok(());
+    }
+}
+
+/// Logging formats are serialized as their templates
+#[cfg(feature = "serde_support")]
+impl serde::Serialize for LoggingFormat {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        return serializer.collect_str(self);
+    }
+}
+
+#[cfg(feature = "serde_support")]
+impl<'de> serde::Deserialize<'de> for LoggingFormat {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let template = String::deserialize(deserializer)?;
+        return template.parse().map_err(serde::de::Error::custom);
+    }
+}
+
 // endregion: LOGGING INFO
 
 // region: LOGGED CALLABLE
 
 /// A struct denoting a logged callable object, like a function, method, or a
-/// closure that implements one of Fn, FnOnce or FnMut.
+/// closure, made with [LoggedCallable::new] or [LoggedCallable::once]. Each run
+/// is logged through the `log` crate when it starts and when it ends, at the
+/// configured level, or at the error level if it fails. The output of a run is
+/// logged as [crate::represent_output] renders it
 #[derive(Debug, Clone)]
-#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
 pub struct LoggedCallable<
-    'a, // the lifetime specifier of the logging format,
-    A,  // arguments as a tuple
-    R,  // return type
-    F,  // Fn trait (like Fn, FnOnce, and FnMut)
+    A, // arguments as a tuple
+    R, // return type
+    F, // handle (a function, a closure, or a OnceHandle of a closure)
 > where
-    F: FnOnce<A, Output = R>,
+    F: Handle<A, Output = R>,
 {
     callable: Callable<A, R, F>,
     logging_data: Option<LoggingData>,
-    logging_format: Option<&'a LoggingFormat>,
+    logging_format: Option<LoggingFormatChoice>, // the format of the job or process if not given
+    log_level: log::Level,
+    log_target: Option<String>, // the module path of this module if not given
+    task_id: usize,
+    start_time: Option<DateTime<Local>>, // when the last run started
+    duration: Option<Duration>,          // how long the last run took
+    represent_output: fn(&R) -> String,  // chosen when the callable is made
 }
 
-pub type LoggedFunction<'a, A, R, F> = LoggedCallable<'a, A, R, F>;
-pub type LoggedMethod<'a, A, R, F> = LoggedCallable<'a, A, R, F>;
-pub type LoggedClosure<'a, A, R, F> = LoggedCallable<'a, A, R, F>;
+pub type LoggedFunction<A, R, F> = LoggedCallable<A, R, F>;
+pub type LoggedMethod<A, R, F> = LoggedCallable<A, R, F>;
+pub type LoggedClosure<A, R, F> = LoggedCallable<A, R, F>;
 
-impl<'a, A, R, F> Deref for LoggedCallable<'a, A, R, F>
+impl<A, R, F> Deref for LoggedCallable<A, R, F>
 where
-    F: FnOnce<A, Output = R>,
+    F: Handle<A, Output = R>,
 {
     type Target = Callable<A, R, F>;
 
@@ -415,40 +1114,181 @@ where
     }
 }
 
-impl<'a, A, R, F> DerefMut for LoggedCallable<'a, A, R, F>
+impl<A, R, F> DerefMut for LoggedCallable<A, R, F>
 where
-    F: FnOnce<A, Output = R>,
+    F: Handle<A, Output = R>,
 {
     fn deref_mut(&mut self) -> &mut Self::Target {
         return &mut self.callable;
     }
 }
 
-impl<'a, A, R, F> LoggedCallable<'a, A, R, F>
+impl<A, R, F> LoggedCallable<A, R, F>
 where
-    F: FnOnce<A, Output = R>,
+    R: Debug + 'static,
+    F: Handle<A, Output = R>,
 {
-
+    /// Creates a new logged callable with the given handle, and the string it
+    /// is logged by. Like [Callable::new], it can be run again
     pub fn new<S: Into<String>>(handle: F, handle_string: S) -> Self {
+        return LoggedCallable::from_callable(Callable::new(handle), handle_string);
+    }
+
+    fn from_callable<S: Into<String>>(callable: Callable<A, R, F>, handle_string: S) -> Self {
         return LoggedCallable {
-            callable: Callable::new(handle),
+            callable,
             logging_data: Some(LoggingData {
                 handle: handle_string.into(),
                 arguments: String::new(),
             }),
             logging_format: None,
+            log_level: log::Level::Info,
+            log_target: None,
+            task_id: crate::generate_task_id(),
+            start_time: None,
+            duration: None,
+            represent_output: crate::represent_output::<R>,
         };
     }
+}
 
+impl<A, R, F> LoggedCallable<A, R, F>
+where
+    F: Handle<A, Output = R>,
+{
     pub fn args<S: Into<String>>(mut self, arguments: A, arguments_string: S) -> Self {
         self.callable = self.callable.args(arguments);
-        if let Some(mut logging_data_inner) = self.logging_data.as_mut() {
+        if let Some(logging_data_inner) = self.logging_data.as_mut() {
             logging_data_inner.arguments = arguments_string.into();
         }
         return self;
     }
 
-    fn generate_log(&self, result: &Result<R, Error>) -> Result<String, Error> {
+    /// Replaces the arguments of the callable, along with their string for
+    /// logging
+    pub fn set_args<S: Into<String>>(&mut self, arguments: A, arguments_string: S) {
+        self.callable.set_args(arguments);
+        if let Some(logging_data_inner) = self.logging_data.as_mut() {
+            logging_data_inner.arguments = arguments_string.into();
+        }
+    }
+
+    /// Sets the format of the line that is logged when a run ends, overriding
+    /// the format of the job that runs the callable and the global default
+    pub fn logging_format<L: Into<Arc<LoggingFormat>>>(mut self, logging_format: L) -> Self {
+        self.logging_format = Some(LoggingFormatChoice::Shared(logging_format.into()));
+        return self;
+    }
+
+    /// Logs the end of each run in the format registered under the given name
+    /// with [LoggingFormat::register]. The name is looked up on every run, and
+    /// runs fail if no format is registered under it
+    pub fn named_logging_format<S: Into<String>>(mut self, name: S) -> Self {
+        self.logging_format = Some(LoggingFormatChoice::Named(name.into()));
+        return self;
+    }
+
+    /// Sets the level at which runs are logged. Failed runs are always logged
+    /// at the error level
+    pub fn level(mut self, level: log::Level) -> Self {
+        self.log_level = level;
+        return self;
+    }
+
+    /// Sets the target that runs are logged to, which is the module path of
+    /// this module by default
+    pub fn target<S: Into<String>>(mut self, target: S) -> Self {
+        self.log_target = Some(target.into());
+        return self;
+    }
+
+    /// Logs a line at the level of the callable, or at the error level
+    fn log(&self, level: log::Level, line: &str) {
+        let target = self.log_target.as_deref().unwrap_or(module_path!());
+        log::log!(target: target, level, "{}", line);
+    }
+}
+
+impl<A, T, E, F> LoggedCallable<A, Result<T, E>, F>
+where
+    T: Debug + 'static,
+    E: std::error::Error + Send + Sync + 'static,
+    F: Handle<A, Output = Result<T, E>>,
+{
+    /// Makes a logged callable whose handle returns a `Result` fail with
+    /// [CallableError::CallableFailed] when the handle returns an `Err`. The
+    /// `Err` fails the run before it is logged, so it is logged as a failure,
+    /// at the error level
+    pub fn fallible(self) -> LoggedCallable<A, T, FallibleHandle<F>> {
+        let AtomicCallable { handle, arguments } = self.callable.atomic_callable;
+        return LoggedCallable {
+            callable: Callable {
+                atomic_callable: AtomicCallable {
+                    handle: handle.map(FallibleHandle),
+                    arguments,
+                },
+            },
+            logging_data: self.logging_data,
+            logging_format: self.logging_format,
+            log_level: self.log_level,
+            log_target: self.log_target,
+            task_id: self.task_id,
+            start_time: self.start_time,
+            duration: self.duration,
+            represent_output: crate::represent_output::<T>,
+        };
+    }
+}
+
+impl<A, R, G> LoggedCallable<A, R, OnceHandle<G>>
+where
+    R: Debug + 'static,
+    OnceHandle<G>: Handle<A, Output = R>,
+{
+    /// Creates a new logged callable with a handle that can only be called
+    /// once, and the string it is logged by. Like [Callable::once], later runs
+    /// fail
+    pub fn once<S: Into<String>>(handle: G, handle_string: S) -> Self {
+        return LoggedCallable::from_callable(Callable::once(handle), handle_string);
+    }
+}
+
+impl<A, R, F> LoggedCallable<A, R, F>
+where
+    F: Handle<A, Output = R>,
+{
+    /// Sets how the output of a run is rendered in the logged line, instead of
+    /// [crate::represent_output]. For example, `Represent::represent` logs an
+    /// output by its [crate::Represent] implementation
+    pub fn represent_output_with(mut self, represent_output: fn(&R) -> String) -> Self {
+        self.represent_output = represent_output;
+        return self;
+    }
+
+    /// The logging format the run is logged with. A named format is looked up
+    /// when the run starts, so that an unknown name fails before the run has
+    /// any side effect
+    fn resolve_logging_format(&self) -> Result<Arc<LoggingFormat>, Error> {
+        return match self.logging_format.as_ref() {
+            Some(LoggingFormatChoice::Shared(logging_format)) => Ok(logging_format.clone()),
+            Some(LoggingFormatChoice::Named(name)) => {
+                LoggingFormat::named(name)
+                    .context(UnknownLoggingFormat { name })
+                    .map_err(Error::from)
+            }
+            None => {
+                Ok(TaskContext::current()
+                    .and_then(|context| context.logging_format().cloned())
+                    .unwrap_or_else(LoggingFormat::global_default))
+            }
+        };
+    }
+
+    fn generate_log(
+        &self,
+        logging_format: &LoggingFormat,
+        result: &Result<R, Error>,
+    ) -> Result<String, Error> {
         let handle_string = &self
             .logging_data
             .as_ref()
@@ -457,80 +1297,131 @@ where
         let arguments_string = &self
             .logging_data
             .as_ref()
-            .context(CallableHandleStringMissing)?
+            .context(CallableArgumentStringMissing)?
             .arguments;
         let output_string = match result.as_ref() {
-            Ok(inner) => inner.represent(),
-            Err(inner) => inner.represent(),
+            Ok(inner) => (self.represent_output)(inner),
+            Err(inner) => inner.to_string(),
+        };
+        let values = LogValues {
+            task_id: self.task_id,
+            handle: handle_string,
+            arguments: arguments_string,
+            output: &output_string,
+            start_time: self.start_time,
+            duration: self.duration,
+            error: result
+                .as_ref()
+                .err()
+                .map(|error| error as &(dyn std::error::Error + 'static)),
+            arguments_type: std::any::type_name::<A>(),
+            return_type: std::any::type_name::<R>(),
         };
 
-        self.logging_format
-            .unwrap_or(&LoggingFormat::default())
+        return Ok(logging_format
             .iter()
-            .fold(Ok(String::new()), |accumulator_string, token| {
-                let intermediate_string = match token {
-                    LoggingFormatToken::Handle => handle_string,
-                    LoggingFormatToken::Args => arguments_string,
-                    LoggingFormatToken::ArbitraryString(arbitrary_string) => arbitrary_string,
-                    LoggingFormatToken::Output => &output_string,
-                };
-                return accumulator_string.map(|mut inner| {
-                    inner.push_str(intermediate_string);
-                    inner
-                });
-            })
-    }
-}
-
-impl<'a, A, R, F> RunAndReturn for LoggedCallable<'a, A, R, F>
+            .map(|token| token.render(&values))
+            .collect());
+    }
+
+    /// Runs the callable, and logs when it starts and when it ends. Returns the
+    /// result along with the line that was logged at the end. With tracing,
+    /// the run is also traced in a span
+    fn logged_run(&mut self) -> Result<(Result<R, Error>, String), Error> {
+        let logging_data = self
+            .logging_data
+            .as_ref()
+            .context(CallableHandleStringMissing)?;
+        let logging_format = self.resolve_logging_format()?;
+        #[cfg(feature = "tracing_support")]
+        let traced_run = TracedRun::start(trace::callable_span(
+            Some(self.task_id),
+            &logging_data.handle,
+            Some(&logging_data.arguments),
+        ));
+        let start_line = format!(
+            "{}({}) started",
+            logging_data.handle, logging_data.arguments
+        );
+        self.log(self.log_level, &start_line);
+        let start = Instant::now();
+        self.start_time = Some(Local::now());
+        #[cfg(feature = "tracing_support")]
+        let result = traced_run.span().in_scope(|| self.callable.call());
+        #[cfg(not(feature = "tracing_support"))]
+        let result = self.callable.call();
+        self.duration = Some(start.elapsed());
+        #[cfg(feature = "tracing_support")]
+        traced_run.complete_result(&result);
+        let end_line = self.generate_log(&logging_format, &result)?;
+        match result {
+            Ok(_) => self.log(self.log_level, &end_line),
+            Err(_) => self.log(log::Level::Error, &end_line),
+        }
+        return Ok((result, end_line));
+    }
+
+    /// Does what [RunAndReturn::run_and_return] does, but also returns the line
+    /// that was logged when the run ended
+    pub fn run_and_log(&mut self) -> Result<(R, String), Error> {
+        let (result, line) = self.logged_run()?;
+        return result.map(|inner| (inner, line));
+    }
+}
+
+impl<A, R, F> RunAndReturn for LoggedCallable<A, R, F>
 where
-    F: FnOnce<A, Output = R>,
+    F: Handle<A, Output = R>,
 {
     type ReturnType = R;
 
-    default fn run_and_return(&mut self) -> Result<Self::ReturnType, Error> {
-        let result = self.callable.run_and_return();
-        self.generate_log(&result)?; // TODO: Use this to log
-        return result;
+    fn run_and_return(&mut self) -> Result<Self::ReturnType, Error> {
+        return self.logged_run()?.0;
     }
 }
 
-impl<'a, A, R, F> Run for LoggedCallable<'a, A, R, F>
+impl<A, R, F> Run for LoggedCallable<A, R, F>
 where
-    F: FnOnce<A, Output = R>,
+    F: Handle<A, Output = R>,
 {
-    default fn run(&mut self) -> Result<(), Error> {
-        let result = self.callable.run_and_return();
-        self.generate_log(&result)?; // TODO: Use this to log
-        return result.map(|_inner| ());
+    fn run(&mut self) -> Result<(), Error> {
+        return self.logged_run()?.0.map(|_inner| ());
     }
 }
 
-impl<'a, A, R, F> RunAndCallback for LoggedCallable<'a, A, R, F>
+impl<A, R, F> RunAndCallback for LoggedCallable<A, R, F>
 where
-    F: FnOnce<A, Output = R>,
+    F: Handle<A, Output = R>,
 {
-    fn run_and_then<C: FnOnce(Self::ReturnType) -> ()>(
-        &mut self,
-        callback: C,
-    ) -> Result<(), Error> {
-        let result = self.callable.run_and_return();
-        self.generate_log(&result)?; // TODO: Use this to log
-        match result {
-            Ok(inner) => Ok(callback(inner)),
-            Err(inner) => Err(inner),
+    fn run_and_then<C: FnOnce(Self::ReturnType)>(&mut self, callback: C) -> Result<(), Error> {
+        let (result, _line) = self.logged_run()?;
+        return result.map(callback);
+    }
+}
+
+impl<A, R, F> AcceptInput for LoggedCallable<A, R, F>
+where
+    A: Debug,
+    F: Handle<A, Output = R>,
+{
+    type Input = A;
+
+    /// Stores the arguments, and logs them as the tuple they form
+    fn accept_input(&mut self, input: Self::Input) {
+        if let Some(logging_data_inner) = self.logging_data.as_mut() {
+            logging_data_inner.arguments = format!("{:?}", input);
         }
+        self.callable.accept_input(input);
     }
 }
 
-impl<'a, A, R, F> RunAndDebug for LoggedCallable<'a, A, R, F>
+impl<A, R, F> RunAndDebug for LoggedCallable<A, R, F>
 where
     R: Debug,
-    F: FnOnce<A, Output = R>,
+    F: Handle<A, Output = R>,
 {
     fn run_and_debug(&mut self) -> Result<String, Error> {
-        let result = self.callable.run_and_return();
-        self.generate_log(&result)?; // TODO: Use this to log
+        let (result, _line) = self.logged_run()?;
         match result {
             Ok(inner) => Ok(format!("{:?}", inner)),
             Err(inner) => Err(inner),
@@ -538,14 +1429,13 @@ where
     }
 }
 
-impl<'a, A, R, F> RunAndDisplay for LoggedCallable<'a, A, R, F>
+impl<A, R, F> RunAndDisplay for LoggedCallable<A, R, F>
 where
     R: Display,
-    F: FnOnce<A, Output = R>,
+    F: Handle<A, Output = R>,
 {
     fn run_and_display(&mut self) -> Result<String, Error> {
-        let result = self.callable.run_and_return();
-        self.generate_log(&result)?; // TODO: Use this to log
+        let (result, _line) = self.logged_run()?;
         match result {
             Ok(inner) => Ok(format!("{}", inner)),
             Err(inner) => Err(inner),
@@ -555,85 +1445,613 @@ where
 
 // endregion: LOGGED CALLABLE
 
+// region: FALLIBLE CALLABLE
+
+/// Wraps a callable whose handle returns a `Result`, so that the callable
+/// returns what is inside an `Ok`, and fails with
+/// [CallableError::CallableFailed] on an `Err`. Jobs and retries then treat
+/// the `Err` as a failure, and the `Err` stays reachable as the source of the
+/// error. Logged callables are made fallible with [LoggedCallable::fallible]
+/// instead, so that the `Err` is logged as a failure
+#[derive(Debug, Clone)]
+pub struct FallibleCallable<C> {
+    callable: C,
+}
+
+impl<C> Deref for FallibleCallable<C> {
+    type Target = C;
+
+    fn deref(&self) -> &Self::Target {
+        return &self.callable;
+    }
+}
+
+impl<C> DerefMut for FallibleCallable<C> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        return &mut self.callable;
+    }
+}
+
+impl<C> FallibleCallable<C> {
+    /// Wraps a callable whose handle returns a `Result`
+    pub fn new(callable: C) -> Self {
+        return FallibleCallable { callable };
+    }
+
+    /// Returns the wrapped callable
+    pub fn into_inner(self) -> C {
+        return self.callable;
+    }
+}
+
+impl<C, T, E> RunAndReturn for FallibleCallable<C>
+where
+    C: RunAndReturn<ReturnType = Result<T, E>>,
+    E: std::error::Error + Send + Sync + 'static,
+{
+    type ReturnType = T;
+
+    fn run_and_return(&mut self) -> Result<Self::ReturnType, Error> {
+        return self
+            .callable
+            .run_and_return()?
+            .map_err(|inner| Box::new(inner) as Box<dyn std::error::Error + Send + Sync>)
+            .context(CallableFailed)
+            .map_err(Error::from);
+    }
+}
+
+impl<C, T, E> Run for FallibleCallable<C>
+where
+    C: RunAndReturn<ReturnType = Result<T, E>>,
+    E: std::error::Error + Send + Sync + 'static,
+{
+    fn run(&mut self) -> Result<(), Error> {
+        return self.run_and_return().map(|_inner| ());
+    }
+}
+
+impl<C, T, E> RunAndCallback for FallibleCallable<C>
+where
+    C: RunAndReturn<ReturnType = Result<T, E>>,
+    E: std::error::Error + Send + Sync + 'static,
+{
+    fn run_and_then<D: FnOnce(Self::ReturnType)>(&mut self, callback: D) -> Result<(), Error> {
+        return self.run_and_return().map(callback);
+    }
+}
+
+impl<C: AcceptInput> AcceptInput for FallibleCallable<C> {
+    type Input = C::Input;
+
+    fn accept_input(&mut self, input: Self::Input) {
+        self.callable.accept_input(input);
+    }
+}
+
+// endregion: FALLIBLE CALLABLE
+
 // region: MACROS
 
+/// Removes the spaces that [stringify] may put around `::`, `.` and the angle
+/// brackets of a turbofish, so that a callee is logged as it is written, as
+/// `std::cmp::max` rather than `std :: cmp :: max`
+#[doc(hidden)]
+pub fn tidy_callee(callee: &str) -> String {
+    let mut tidy = callee.to_string();
+    for (spaced, tight) in [
+        (" :: ", "::"),
+        (" ::", "::"),
+        (":: ", "::"),
+        (" . ", "."),
+        (" .", "."),
+        ("::< ", "::<"),
+    ] {
+        tidy = tidy.replace(spaced, tight);
+    }
+    tidy = tidy.replace(" >::", ">::").replace(" >.", ">.");
+    if tidy.ends_with(" >") {
+        tidy.replace_range(tidy.len() - 2.., ">");
+    }
+    return tidy;
+}
+
+/// Makes a [Callable] out of a call expression, which is evaluated when the
+/// callable is run. Like [Callable::once], the callable can be run once. The
+/// function being called can be a path with generics, as in
+/// `Vec::<i32>::new()`, a method on any expression, as in `self.items.pop()`,
+/// or a parenthesized closure. The arguments may end with a comma
+///
+/// ```
+/// use running::{callable, RunAndReturn};
+///
+/// let items = vec![1, 2, 3];
+/// let mut with_capacity = callable!(Vec::<i32>::with_capacity(3));
+/// assert_eq!(with_capacity.run_and_return().unwrap(), Vec::<i32>::new());
+/// let mut max = callable!(items.iter().map(|item| item * 2).max());
+/// assert_eq!(max.run_and_return().unwrap(), Some(6));
+/// let mut difference = callable!((|left: i32, right: i32| left - right)(5, 3,));
+/// assert_eq!(difference.run_and_return().unwrap(), 2);
+/// ```
+///
+/// The call must end with its arguments in parentheses
+///
+/// ```compile_fail
+/// use running::callable;
+///
+/// let max = callable!(std::cmp::max);
+/// ```
 #[macro_export]
-macro_rules! callable{
-    ( $first_parent:ident $(:: $path_fragment_type_a:ident)* $(. $path_fragment_type_b:ident)* ( $($arguments:expr),* ) ) => {
+macro_rules! callable {
+    // Moves tokens into the callee until only the parenthesized arguments are
+    // left
+    (@munch [$($callee:tt)*] ( $($arguments:tt)* )) => {
+        $crate::callable!(@build [$($callee)*] $($arguments)*)
+    };
+    (@munch [$($callee:tt)*] $next:tt $($rest:tt)+) => {
+        $crate::callable!(@munch [$($callee)* $next] $($rest)+)
+    };
+    (@build [$($callee:tt)+] $($arguments:expr),* $(,)?) => {
         {
-            use $crate::callable::Callable;
-
-            let callback = || -> _ {
-                $first_parent $(:: $path_fragment_type_a)* $(. $path_fragment_type_b)* ($($arguments),*)
-            };
-
-            Callable::new(callback).args(())
+            let callback = || -> _ { $($callee)+ ($($arguments),*) };
+            $crate::callable::Callable::once(callback)
         }
     };
+    ($($call:tt)+) => {
+        $crate::callable!(@munch [] $($call)+)
+    };
 }
 
 #[macro_export]
 macro_rules! function {
-    ($( $arg:expr ),*) => {
-        callable!($($arg)*);
+    ($($call:tt)+) => {
+        $crate::callable!($($call)+)
     };
 }
 
 #[macro_export]
 macro_rules! method {
-    ($( $arg:expr ),*) => {
-        callable!($($arg)*);
+    ($($call:tt)+) => {
+        $crate::callable!($($call)+)
     };
 }
 
 #[macro_export]
 macro_rules! closure {
-    ($( $arg:expr ),*) => {
-        callable!($($arg)*);
+    ($($call:tt)+) => {
+        $crate::callable!($($call)+)
     };
 }
 
-
+/// Makes a [LoggedCallable] out of a call expression, like [callable!], with
+/// the callee and the arguments as written for the handle and argument
+/// strings. The call can be followed by `format = ...` to set a logging
+/// format, and by `level = ...` to set the log level, in any order
+///
+/// ```
+/// use running::callable::LoggingFormat;
+/// use running::{logged_callable, RunAndReturn};
+///
+/// let logging_format: LoggingFormat = "{handle}({args}) -> {output}".parse().unwrap();
+/// let mut max = logged_callable!(std::cmp::max(1, 2));
+/// assert_eq!(max.run_and_return().unwrap(), 2);
+/// let mut max = logged_callable!(std::cmp::max(1, 2,), format = logging_format.clone());
+/// assert_eq!(max.run_and_log().unwrap().1, "std::cmp::max(1, 2) -> 2");
+/// let mut length = logged_callable!((|text: &str| text.len())("four"), level = log::Level::Debug);
+/// assert_eq!(length.run_and_return().unwrap(), 4);
+/// let mut with_capacity = logged_callable!(
+///     Vec::<u8>::with_capacity(1),
+///     level = log::Level::Trace,
+///     format = logging_format,
+/// );
+/// assert_eq!(
+///     with_capacity.run_and_log().unwrap().1,
+///     "Vec::<u8>::with_capacity(1) -> []"
+/// );
+/// ```
+///
+/// Only `format` and `level` can be set
+///
+/// ```compile_fail
+/// use running::logged_callable;
+///
+/// let max = logged_callable!(std::cmp::max(1, 2), colour = "red");
+/// ```
 #[macro_export]
-macro_rules! logged_callable{
-    ( $first_parent:ident $(:: $path_fragment_type_a:ident)* $(. $path_fragment_type_b:ident)* ( $($arguments:expr),* ) ) => {
+macro_rules! logged_callable {
+    (@munch [$($callee:tt)*] ( $($arguments:tt)* ) $(, $option:ident = $value:expr)* $(,)?) => {
         {
-            use $crate::callable::LoggedCallable;
-
-            let callback = || -> _ {
-                $first_parent $(:: $path_fragment_type_a)* $(. $path_fragment_type_b)* ($($arguments),*)
-            };
-            Callable::new(callback, stringify!($first_parent$(::$path_fragment_type_a)*$(.$path_fragment_type_b)*)).args((), stringify!($($arguments),*))
+            let logged_callable = $crate::logged_callable!(@build [$($callee)*] $($arguments)*);
+            $(let logged_callable = $crate::logged_callable!(@option logged_callable, $option, $value);)*
+            logged_callable
+        }
+    };
+    (@munch [$($callee:tt)*] $next:tt $($rest:tt)+) => {
+        $crate::logged_callable!(@munch [$($callee)* $next] $($rest)+)
+    };
+    (@build [$($callee:tt)+] $($arguments:expr),* $(,)?) => {
+        {
+            let callback = || -> _ { $($callee)+ ($($arguments),*) };
+            $crate::callable::LoggedCallable::once(callback, $crate::callable::tidy_callee(stringify!($($callee)+)))
+                .args((), stringify!($($arguments),*))
         }
     };
+    (@option $logged_callable:ident, format, $logging_format:expr) => {
+        $logged_callable.logging_format($logging_format)
+    };
+    (@option $logged_callable:ident, level, $level:expr) => {
+        $logged_callable.level($level)
+    };
+    ($($call:tt)+) => {
+        $crate::logged_callable!(@munch [] $($call)+)
+    };
 }
 
 #[macro_export]
 macro_rules! logged_function {
-    ($( $arg:expr ),*) => {
-        logged_callable!($($arg)*);
+    ($($call:tt)+) => {
+        $crate::logged_callable!($($call)+)
     };
 }
 
 #[macro_export]
 macro_rules! logged_method {
-    ($( $arg:expr ),*) => {
-        logged_callable!($($arg)*);
+    ($($call:tt)+) => {
+        $crate::logged_callable!($($call)+)
     };
 }
 
 #[macro_export]
 macro_rules! logged_closure {
-    ($( $arg:expr ),*) => {
-        logged_callable!($($arg)*);
+    ($($call:tt)+) => {
+        $crate::logged_callable!($($call)+)
     };
 }
 
-
 // endregion: MACROS
 
 // region: TESTS
 
+#[cfg(test)]
+mod tests {
+
+    // IMPORTS
+
+    use super::{
+        tidy_callee, Callable, CallableError, LoggedCallable, LoggingFormat, LoggingFormatError,
+        LoggingFormatToken,
+    };
+    use crate::tests::capture_logs;
+    use crate::{RunAndReturn, RunRepeatedly, RunningError};
+    use log::Level;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    // TESTS
+
+    #[test]
+    fn rerun_with_cloned_arguments() {
+        let mut callable = Callable::new(|left: usize, right: usize| left + right).args((2, 3));
+        assert_eq!(callable.run_n(3).unwrap(), vec![5, 5, 5]);
+
+        callable.set_args((4, 5));
+        assert_eq!(callable.run_and_return().unwrap(), 9);
+    }
+
+    #[test]
+    fn rerun_fn_mut() {
+        let mut count = 0;
+        let mut callable = Callable::new(|step: usize| {
+            count += step;
+            count
+        })
+        .args((2,));
+        assert_eq!(
+            callable.repeat_while(|&count| count < 6).unwrap(),
+            vec![2, 4, 6]
+        );
+    }
+
+    #[test]
+    fn panic_details() {
+        let mut callable = Callable::new(|| -> () { panic!("Panicking test...") });
+        let error = callable.run_and_return().unwrap_err();
+        match &error {
+            RunningError::Callable(CallableError::CallablePanicked {
+                message,
+                location,
+                thread,
+                ..
+            }) => {
+                assert_eq!(message, "Panicking test...");
+                assert!(location.as_ref().unwrap().starts_with("src/callable.rs:"));
+                assert_eq!(thread.as_deref(), Some("callable::tests::panic_details"));
+            }
+            _ => panic!("Expected the callable to panic, but got: {}", error),
+        }
+
+        let mut logged_callable =
+            LoggedCallable::new(|code: i32| -> () { panic!("Exit code {}", code) }, "exit")
+                .args((3,), "3");
+        let result = logged_callable.run_and_return();
+        let logging_format = logged_callable.resolve_logging_format().unwrap();
+        let log = logged_callable
+            .generate_log(&logging_format, &result)
+            .unwrap();
+        assert!(log.starts_with("exit(3) -> Callable panicked in thread"));
+        assert!(log.ends_with(": Exit code 3"));
+    }
+
+    #[test]
+    fn fallible() {
+        let mut callable = Callable::new(|text: &'static str| text.parse::<i32>()).fallible();
+        callable.set_args(("42",));
+        assert_eq!(callable.run_and_return().unwrap(), 42);
+
+        callable.set_args(("forty-two",));
+        let error = callable.run_and_return().unwrap_err();
+        let source = std::error::Error::source(&error).unwrap();
+        assert!(source.downcast_ref::<std::num::ParseIntError>().is_some());
+        assert!(error
+            .to_string()
+            .starts_with("Callable failed: invalid digit"));
+    }
+
+    #[test]
+    fn rerun_fn_once() {
+        let message = String::from("Hello");
+        let mut callable = Callable::once(move || message);
+        assert_eq!(callable.run_and_return().unwrap(), "Hello");
+        let error = callable.run_and_return().unwrap_err();
+        assert!(matches!(
+            error,
+            RunningError::Callable(CallableError::CallableArgumentsMissing { .. })
+        ));
+    }
+
+    #[test]
+    fn logging_format_template() {
+        let template = "[{task_id}] {handle:>6}({args:.3}) -> {output:^6}|{{literal}}";
+        let logging_format: LoggingFormat = template.parse().unwrap();
+        assert_eq!(logging_format.to_string(), template);
+        assert_eq!(
+            logging_format[2],
+            LoggingFormatToken::ArbitraryString(String::from("] "))
+        );
+
+        let mut logged_callable = LoggedCallable::new(|left: i32, right: i32| left * right, "mul")
+            .args((6, 7), "6, 7")
+            .logging_format(logging_format.clone());
+        let (_, line) = logged_callable.run_and_log().unwrap();
+        assert!(line.ends_with("]    mul(6, ) ->   42  |{literal}"));
+
+        let error = "{handle} in {elapsed}"
+            .parse::<LoggingFormat>()
+            .unwrap_err();
+        assert!(
+            matches!(error, LoggingFormatError::UnknownPlaceholder { ref name, position: 12, .. } if name == "elapsed")
+        );
+        assert!(matches!(
+            "{handle:x}".parse::<LoggingFormat>(),
+            Err(LoggingFormatError::InvalidSpecifier { .. })
+        ));
+        assert!(matches!(
+            "{handle".parse::<LoggingFormat>(),
+            Err(LoggingFormatError::UnclosedPlaceholder { position: 0, .. })
+        ));
+        assert!(matches!(
+            "handle}".parse::<LoggingFormat>(),
+            Err(LoggingFormatError::UnmatchedBrace { position: 6, .. })
+        ));
+    }
+
+    #[test]
+    fn run_details_tokens() {
+        let template = "{status} {args_type} -> {return_type} [{start_time(%Y)}|{end_time:.4}] on {thread}: {error_chain}";
+        let logging_format: LoggingFormat = template.parse().unwrap();
+        assert_eq!(logging_format.to_string(), template);
+
+        let mut logged_callable =
+            LoggedCallable::new(|text: &'static str| text.parse::<u8>(), "parse")
+                .args(("256",), "\"256\"")
+                .logging_format(logging_format.clone())
+                .fallible();
+        let logs = capture_logs(|| assert!(logged_callable.run_and_return().is_err()));
+        let (level, line) = &logs[1];
+        assert_eq!(*level, Level::Error);
+        assert!(line.starts_with("failed (&str,) -> u8 ["));
+        assert!(line.ends_with("] on callable::tests::run_details_tokens: Callable failed: number too large to fit in target type"));
+
+        logged_callable.set_args(("255",), "\"255\"");
+        let logs = capture_logs(|| assert_eq!(logged_callable.run_and_return().unwrap(), 255));
+        let year = chrono::Local::now().format("%Y").to_string();
+        assert_eq!(
+            logs[1],
+            (
+                Level::Info,
+                format!(
+                    "succeeded (&str,) -> u8 [{0}|{0}] on {1}: ",
+                    year, "callable::tests::run_details_tokens"
+                )
+            )
+        );
+
+        assert!(matches!(
+            "{start_time(%Q)}".parse::<LoggingFormat>(),
+            Err(LoggingFormatError::InvalidTimestampFormat { .. })
+        ));
+    }
+
+    #[test]
+    fn error_chain_with_shared_suffix() {
+        #[derive(Debug)]
+        struct Upload(std::io::Error);
+
+        impl std::fmt::Display for Upload {
+            fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                return write!(formatter, "upload failed");
+            }
+        }
+
+        impl std::error::Error for Upload {
+            fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+                return Some(&self.0);
+            }
+        }
+
+        let logging_format: LoggingFormat = "{error_chain}".parse().unwrap();
+        let mut logged_callable = LoggedCallable::new(
+            || Err::<(), _>(Upload(std::io::Error::other("failed"))),
+            "upload",
+        )
+        .logging_format(logging_format)
+        .fallible();
+        let logs = capture_logs(|| assert!(logged_callable.run_and_return().is_err()));
+        assert_eq!(logs[1].1, "Callable failed: upload failed: failed");
+    }
+
+    #[test]
+    fn named_logging_format() {
+        LoggingFormat::register(
+            "callable-tests",
+            "{handle}: {output}".parse::<LoggingFormat>().unwrap(),
+        );
+        let mut logged_callable =
+            LoggedCallable::new(|| 42, "answer").named_logging_format("callable-tests");
+        assert_eq!(logged_callable.run_and_log().unwrap().1, "answer: 42");
+
+        let runs = Arc::new(AtomicUsize::new(0));
+        let counted_runs = runs.clone();
+        let mut logged_callable =
+            LoggedCallable::new(move || counted_runs.fetch_add(1, Ordering::SeqCst), "count")
+                .named_logging_format("unregistered");
+        let error = logged_callable.run_and_log().unwrap_err();
+        assert!(matches!(
+            error,
+            RunningError::LoggingFormat(LoggingFormatError::UnknownLoggingFormat { .. })
+        ));
+        assert_eq!(runs.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn macros() {
+        struct Holder {
+            items: Vec<i32>,
+        }
+
+        impl Holder {
+            fn last_doubled(&mut self, factor: i32) -> Option<i32> {
+                let mut callable = method!(self.items.pop().map(|item| item * factor));
+                return callable.run_and_return().unwrap();
+            }
+        }
+
+        assert_eq!(function!(std::cmp::max(1, 2)).run_and_return().unwrap(), 2);
+        assert_eq!(function!(std::cmp::max(1, 2,)).run_and_return().unwrap(), 2);
+        assert_eq!(
+            function!(Vec::<i32>::with_capacity(3))
+                .run_and_return()
+                .unwrap(),
+            Vec::<i32>::new()
+        );
+        assert_eq!(
+            callable!(std::iter::once::<u8>(7).chain(Some(7)).sum::<u8>())
+                .run_and_return()
+                .unwrap(),
+            14
+        );
+        assert_eq!(
+            closure!((|left: i32, right: i32| left - right)(5, 3))
+                .run_and_return()
+                .unwrap(),
+            2
+        );
+
+        let mut vector = vec![1, 2, 3];
+        assert_eq!(method!(vector.pop()).run_and_return().unwrap(), Some(3));
+        let mut holder = Holder { items: vec![4, 5] };
+        assert_eq!(holder.last_doubled(2), Some(10));
+
+        let logging_format: LoggingFormat = "{handle}|{args}|{output}".parse().unwrap();
+        let mut logged_callable = logged_function!(
+            std::cmp::max(1, 2),
+            format = logging_format.clone(),
+            level = log::Level::Debug,
+        );
+        let (output, line) = logged_callable.run_and_log().unwrap();
+        assert_eq!(output, 2);
+        assert_eq!(line, "std::cmp::max|1, 2|2");
+        assert_eq!(tidy_callee("std :: cmp :: max"), "std::cmp::max");
+        assert_eq!(tidy_callee("Vec :: < i32 > :: new"), "Vec::<i32>::new");
+        assert_eq!(
+            tidy_callee("self . items . pop () . sum :: < u8 >"),
+            "self.items.pop ().sum::<u8>"
+        );
+        assert_eq!(
+            tidy_callee("(| left : i32 | left - 1)"),
+            "(| left : i32 | left - 1)"
+        );
+
+        let mut logged_callable = logged_method!(vector.pop());
+        assert_eq!(logged_callable.run_and_log().unwrap().0, Some(2));
+        let mut logged_callable =
+            logged_closure!((|text: &str| text.len())("four"), level = log::Level::Trace);
+        assert_eq!(logged_callable.run_and_return().unwrap(), 4);
+        let mut logged_callable =
+            logged_callable!(Vec::<u8>::with_capacity(1,), format = logging_format);
+        assert_eq!(logged_callable.run_and_log().unwrap().0, Vec::<u8>::new());
+    }
+
+    #[test]
+    fn represent_output() {
+        #[derive(Debug)]
+        struct Point {
+            x: i32,
+            y: i32,
+        }
+
+        let logging_format: LoggingFormat = "{handle} -> {output}".parse().unwrap();
+        let mut logged_callable = LoggedCallable::new(|| Point { x: 1, y: 2 }, "point")
+            .logging_format(logging_format.clone());
+        let logs = capture_logs(|| {
+            let point = logged_callable.run_and_return().unwrap();
+            assert_eq!((point.x, point.y), (1, 2));
+        });
+        assert_eq!(
+            logs,
+            vec![
+                (Level::Info, String::from("point() started")),
+                (Level::Info, String::from("point -> Point { x: 1, y: 2 }")),
+            ]
+        );
+
+        let mut logged_callable =
+            LoggedCallable::new(|| String::from("text"), "text").logging_format(logging_format);
+        assert_eq!(logged_callable.run_and_log().unwrap().1, "text -> text");
+        let mut logged_callable =
+            logged_callable.represent_output_with(|output| output.to_uppercase());
+        assert_eq!(logged_callable.run_and_log().unwrap().1, "text -> TEXT");
+    }
+
+    #[test]
+    fn run_and_log() {
+        let mut logged_callable =
+            LoggedCallable::new(|left: i32, right: i32| left * right, "multiply")
+                .args((6, 7), "6, 7")
+                .level(log::Level::Debug)
+                .target("running::tests");
+        let (output, line) = logged_callable.run_and_log().unwrap();
+        assert_eq!(output, 42);
+        let logging_format = logged_callable.resolve_logging_format().unwrap();
+        assert_eq!(
+            line,
+            logged_callable
+                .generate_log(&logging_format, &Ok(42))
+                .unwrap()
+        );
+    }
+}
+
 // #[cfg(test)]
 // mod tests {
 
@@ -749,8 +2167,8 @@ macro_rules! logged_closure {
 //     #[cfg(feature = "logging")]
 //     fn try_string_from() {
 //         let value: isize = 5;
-//         assert_eq!(String::from("5"), crate::try_string_from(&value).unwrap())
-//     }
+//         assert_eq!(String::from("5"),
+// crate::try_string_from(&value).unwrap())     }
 // }
 
 // endregion: TESTS
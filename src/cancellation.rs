@@ -0,0 +1,134 @@
+// IMPORTS
+use crate::runnable::TaskContext;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+// STRUCT DECLARATIONS
+
+/// The state shared by the clones of a [CancellationToken]
+struct CancellationState {
+    cancelled: AtomicBool,
+    parents: Vec<Arc<CancellationState>>,
+}
+
+/// A token through which a running job, command, or callable can be asked to
+/// stop. Cancellation is cooperative: jobs check the token between tasks, and
+/// commands are killed when it fires, but callables have to poll it
+/// themselves. Clones of a token share the same state, so a clone can be
+/// cancelled from a UI button or a signal handler on another thread
+#[derive(Clone)]
+pub struct CancellationToken {
+    state: Arc<CancellationState>,
+}
+
+// STRUCT IMPLEMENTATIONS
+
+impl CancellationState {
+    fn is_cancelled(&self) -> bool {
+        return self.cancelled.load(Ordering::SeqCst)
+            || self.parents.iter().any(|parent| parent.is_cancelled());
+    }
+}
+
+impl CancellationToken {
+    /// Creates a token that is not cancelled
+    pub fn new() -> Self {
+        return CancellationToken {
+            state: Arc::new(CancellationState {
+                cancelled: AtomicBool::new(false),
+                parents: Vec::new(),
+            }),
+        };
+    }
+
+    /// Creates a token that is cancelled when this token is cancelled, but
+    /// that can also be cancelled on its own without affecting this token
+    pub fn child_token(&self) -> Self {
+        return CancellationToken::child_of([self]);
+    }
+
+    /// Creates a token that is cancelled when any of the given tokens is
+    /// cancelled
+    pub(crate) fn child_of<'t, I: IntoIterator<Item = &'t CancellationToken>>(parents: I) -> Self {
+        return CancellationToken {
+            state: Arc::new(CancellationState {
+                cancelled: AtomicBool::new(false),
+                parents: parents
+                    .into_iter()
+                    .map(|parent| parent.state.clone())
+                    .collect(),
+            }),
+        };
+    }
+
+    /// Cancels the token, its clones, and its child tokens. Cancelling a token
+    /// more than once has no further effect
+    pub fn cancel(&self) {
+        self.state.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// Whether the token, or any token it is a child of, was cancelled
+    pub fn is_cancelled(&self) -> bool {
+        return self.state.is_cancelled();
+    }
+
+    /// The token of the job task running on the current thread. It is cancelled
+    /// when the job is cancelled, or when it stops early because a sibling task
+    /// failed. Outside of a job, returns a token that is never cancelled
+    pub fn current() -> Self {
+        return TaskContext::current()
+            .map(|context| context.cancellation_token().clone())
+            .unwrap_or_default();
+    }
+}
+
+impl Default for CancellationToken {
+    fn default() -> Self {
+        return CancellationToken::new();
+    }
+}
+
+// TRAIT IMPLEMENTATIONS
+
+impl fmt::Debug for CancellationToken {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return formatter
+            .debug_struct("CancellationToken")
+            .field("cancelled", &self.is_cancelled())
+            .finish();
+    }
+}
+
+// TESTS
+
+#[cfg(test)]
+mod tests {
+
+    // IMPORTS
+
+    use super::CancellationToken;
+
+    // TESTS
+
+    #[test]
+    fn child_tokens() {
+        let parent = CancellationToken::new();
+        let child = parent.child_token();
+        let sibling = parent.child_token();
+
+        child.cancel();
+        assert!(child.is_cancelled());
+        assert!(!parent.is_cancelled());
+        assert!(!sibling.is_cancelled());
+
+        parent.clone().cancel();
+        assert!(parent.is_cancelled());
+        assert!(sibling.is_cancelled());
+    }
+
+    #[test]
+    fn current_outside_of_a_job() {
+        assert!(!CancellationToken::current().is_cancelled());
+    }
+}
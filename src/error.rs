@@ -0,0 +1,369 @@
+// IMPORTS
+use crate::callable::{CallableError, LoggingFormatError};
+use crate::executor::ExecutorError;
+use crate::instruction::CommandError;
+#[cfg(feature = "manifest_support")]
+use crate::manifest::ManifestError;
+use crate::retry::RetryError;
+use crate::runnable::JobError;
+#[cfg(feature = "signal_support")]
+use crate::signal::SignalError;
+use snafu::{Backtrace, ErrorCompat};
+use std::fmt::{self, Display};
+
+// STRUCT DECLARATIONS
+
+/// The error of every fallible operation of this crate, under the name
+/// [crate::Error]. Each variant wraps the error of the module it comes from,
+/// and is transparent: it displays as that error, its source is the source of
+/// that error, and so is its backtrace. Backtraces are only captured with the
+/// `backtrace_support` feature, and are empty otherwise. It is
+/// `Send + Sync + 'static`, so it can be moved across threads and async
+/// tasks, or into other error types
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum RunningError {
+    Callable(CallableError),
+    LoggingFormat(LoggingFormatError),
+    Command(CommandError),
+    Job(JobError),
+    Retry(RetryError),
+    Executor(ExecutorError),
+    #[cfg(feature = "signal_support")]
+    Signal(SignalError),
+    #[cfg(feature = "manifest_support")]
+    Manifest(ManifestError),
+    Io(std::io::Error),
+}
+
+/// A stable, machine-readable code for each kind of [RunningError]. Codes are
+/// never renamed or reused, and their strings, like `command.failed`, are the
+/// module of the error and the kind of failure
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorCode {
+    CallableHandleMissing,
+    CallableArgumentsMissing,
+    CallablePanicked,
+    CallableFailed,
+    CallableHandleStringMissing,
+    CallableArgumentStringMissing,
+    LoggingFormatUnknownPlaceholder,
+    LoggingFormatInvalidSpecifier,
+    LoggingFormatInvalidTimestampFormat,
+    LoggingFormatUnknown,
+    LoggingFormatUnclosedPlaceholder,
+    LoggingFormatUnmatchedBrace,
+    CommandStartFailed,
+    CommandFailed,
+    CommandKilled,
+    JobFailed,
+    JobCancelled,
+    JobInterrupted,
+    JobTaskMissing,
+    JobTaskAmbiguous,
+    JobDependencyMissing,
+    JobDependencyAmbiguous,
+    JobUpstreamOutputMissing,
+    JobDependencyCycle,
+    JobTaskTimedOut,
+    RetryNotRerunnable,
+    ExecutorSpawnedTaskLost,
+    ExecutorRunnableMissing,
+    ExecutorStartFailed,
+    SignalHandlerFailed,
+    ManifestUnreadable,
+    ManifestFormatUnknown,
+    ManifestInvalid,
+    ManifestTaskDuplicated,
+    ManifestTaskInvalid,
+    Io,
+}
+
+// STRUCT IMPLEMENTATIONS
+
+impl RunningError {
+    /// The code of the kind of error
+    pub fn code(&self) -> ErrorCode {
+        return match self {
+            RunningError::Callable(callable_error) => {
+                match callable_error {
+                    CallableError::CallableHandleMissing { .. } => ErrorCode::CallableHandleMissing,
+                    CallableError::CallableArgumentsMissing { .. } => {
+                        ErrorCode::CallableArgumentsMissing
+                    }
+                    CallableError::CallablePanicked { .. } => ErrorCode::CallablePanicked,
+                    CallableError::CallableFailed { .. } => ErrorCode::CallableFailed,
+                    CallableError::CallableHandleStringMissing { .. } => {
+                        ErrorCode::CallableHandleStringMissing
+                    }
+                    CallableError::CallableArgumentStringMissing { .. } => {
+                        ErrorCode::CallableArgumentStringMissing
+                    }
+                }
+            }
+            RunningError::LoggingFormat(logging_format_error) => {
+                match logging_format_error {
+                    LoggingFormatError::UnknownPlaceholder { .. } => {
+                        ErrorCode::LoggingFormatUnknownPlaceholder
+                    }
+                    LoggingFormatError::InvalidSpecifier { .. } => {
+                        ErrorCode::LoggingFormatInvalidSpecifier
+                    }
+                    LoggingFormatError::InvalidTimestampFormat { .. } => {
+                        ErrorCode::LoggingFormatInvalidTimestampFormat
+                    }
+                    LoggingFormatError::UnknownLoggingFormat { .. } => {
+                        ErrorCode::LoggingFormatUnknown
+                    }
+                    LoggingFormatError::UnclosedPlaceholder { .. } => {
+                        ErrorCode::LoggingFormatUnclosedPlaceholder
+                    }
+                    LoggingFormatError::UnmatchedBrace { .. } => {
+                        ErrorCode::LoggingFormatUnmatchedBrace
+                    }
+                }
+            }
+            RunningError::Command(command_error) => {
+                match command_error {
+                    CommandError::CommandStartFailed { .. } => ErrorCode::CommandStartFailed,
+                    CommandError::CommandFailed { .. } => ErrorCode::CommandFailed,
+                    CommandError::CommandKilled { .. } => ErrorCode::CommandKilled,
+                }
+            }
+            RunningError::Job(job_error) => {
+                match job_error {
+                    JobError::JobFailed { .. } => ErrorCode::JobFailed,
+                    JobError::JobCancelled { .. } => ErrorCode::JobCancelled,
+                    JobError::JobInterrupted { .. } => ErrorCode::JobInterrupted,
+                    JobError::TaskMissing { .. } => ErrorCode::JobTaskMissing,
+                    JobError::TaskAmbiguous { .. } => ErrorCode::JobTaskAmbiguous,
+                    JobError::DependencyMissing { .. } => ErrorCode::JobDependencyMissing,
+                    JobError::DependencyAmbiguous { .. } => ErrorCode::JobDependencyAmbiguous,
+                    JobError::UpstreamOutputMissing { .. } => ErrorCode::JobUpstreamOutputMissing,
+                    JobError::DependencyCycle { .. } => ErrorCode::JobDependencyCycle,
+                    JobError::TaskTimedOut { .. } => ErrorCode::JobTaskTimedOut,
+                }
+            }
+            RunningError::Retry(retry_error) => {
+                match retry_error {
+                    RetryError::NotRerunnable { .. } => ErrorCode::RetryNotRerunnable,
+                }
+            }
+            RunningError::Executor(executor_error) => {
+                match executor_error {
+                    ExecutorError::SpawnedTaskLost { .. } => ErrorCode::ExecutorSpawnedTaskLost,
+                    ExecutorError::RunnableMissing { .. } => ErrorCode::ExecutorRunnableMissing,
+                    ExecutorError::ExecutorStartFailed { .. } => ErrorCode::ExecutorStartFailed,
+                }
+            }
+            #[cfg(feature = "signal_support")]
+            RunningError::Signal(signal_error) => {
+                match signal_error {
+                    SignalError::SignalHandlerFailed { .. } => ErrorCode::SignalHandlerFailed,
+                }
+            }
+            #[cfg(feature = "manifest_support")]
+            RunningError::Manifest(manifest_error) => {
+                match manifest_error {
+                    ManifestError::ManifestUnreadable { .. } => ErrorCode::ManifestUnreadable,
+                    ManifestError::ManifestFormatUnknown { .. } => ErrorCode::ManifestFormatUnknown,
+                    ManifestError::ManifestInvalid { .. } => ErrorCode::ManifestInvalid,
+                    ManifestError::TaskDuplicated { .. } => ErrorCode::ManifestTaskDuplicated,
+                    ManifestError::TaskInvalid { .. } => ErrorCode::ManifestTaskInvalid,
+                }
+            }
+            RunningError::Io(_) => ErrorCode::Io,
+        };
+    }
+
+    /// Whether the error is a timeout, or a cancellation of a command or a
+    /// job, including by a signal
+    pub fn is_cancellation(&self) -> bool {
+        return matches!(
+            self.code(),
+            ErrorCode::CommandKilled
+                | ErrorCode::JobCancelled
+                | ErrorCode::JobInterrupted
+                | ErrorCode::JobTaskTimedOut
+        );
+    }
+
+    /// The error that the variant wraps
+    fn inner(&self) -> &(dyn std::error::Error + Send + Sync + 'static) {
+        return match self {
+            RunningError::Callable(callable_error) => callable_error,
+            RunningError::LoggingFormat(logging_format_error) => logging_format_error,
+            RunningError::Command(command_error) => command_error,
+            RunningError::Job(job_error) => job_error,
+            RunningError::Retry(retry_error) => retry_error,
+            RunningError::Executor(executor_error) => executor_error,
+            #[cfg(feature = "signal_support")]
+            RunningError::Signal(signal_error) => signal_error,
+            #[cfg(feature = "manifest_support")]
+            RunningError::Manifest(manifest_error) => manifest_error,
+            RunningError::Io(io_error) => io_error,
+        };
+    }
+}
+
+impl ErrorCode {
+    /// The stable string of the code
+    pub fn as_str(&self) -> &'static str {
+        return match self {
+            ErrorCode::CallableHandleMissing => "callable.handle_missing",
+            ErrorCode::CallableArgumentsMissing => "callable.arguments_missing",
+            ErrorCode::CallablePanicked => "callable.panicked",
+            ErrorCode::CallableFailed => "callable.failed",
+            ErrorCode::CallableHandleStringMissing => "callable.handle_string_missing",
+            ErrorCode::CallableArgumentStringMissing => "callable.argument_string_missing",
+            ErrorCode::LoggingFormatUnknownPlaceholder => "logging_format.unknown_placeholder",
+            ErrorCode::LoggingFormatInvalidSpecifier => "logging_format.invalid_specifier",
+            ErrorCode::LoggingFormatInvalidTimestampFormat => {
+                "logging_format.invalid_timestamp_format"
+            }
+            ErrorCode::LoggingFormatUnknown => "logging_format.unknown",
+            ErrorCode::LoggingFormatUnclosedPlaceholder => "logging_format.unclosed_placeholder",
+            ErrorCode::LoggingFormatUnmatchedBrace => "logging_format.unmatched_brace",
+            ErrorCode::CommandStartFailed => "command.start_failed",
+            ErrorCode::CommandFailed => "command.failed",
+            ErrorCode::CommandKilled => "command.killed",
+            ErrorCode::JobFailed => "job.failed",
+            ErrorCode::JobCancelled => "job.cancelled",
+            ErrorCode::JobInterrupted => "job.interrupted",
+            ErrorCode::JobTaskMissing => "job.task_missing",
+            ErrorCode::JobTaskAmbiguous => "job.task_ambiguous",
+            ErrorCode::JobDependencyMissing => "job.dependency_missing",
+            ErrorCode::JobDependencyAmbiguous => "job.dependency_ambiguous",
+            ErrorCode::JobUpstreamOutputMissing => "job.upstream_output_missing",
+            ErrorCode::JobDependencyCycle => "job.dependency_cycle",
+            ErrorCode::JobTaskTimedOut => "job.task_timed_out",
+            ErrorCode::RetryNotRerunnable => "retry.not_rerunnable",
+            ErrorCode::ExecutorSpawnedTaskLost => "executor.spawned_task_lost",
+            ErrorCode::ExecutorRunnableMissing => "executor.runnable_missing",
+            ErrorCode::ExecutorStartFailed => "executor.start_failed",
+            ErrorCode::SignalHandlerFailed => "signal.handler_failed",
+            ErrorCode::ManifestUnreadable => "manifest.unreadable",
+            ErrorCode::ManifestFormatUnknown => "manifest.format_unknown",
+            ErrorCode::ManifestInvalid => "manifest.invalid",
+            ErrorCode::ManifestTaskDuplicated => "manifest.task_duplicated",
+            ErrorCode::ManifestTaskInvalid => "manifest.task_invalid",
+            ErrorCode::Io => "io",
+        };
+    }
+}
+
+// TRAIT IMPLEMENTATIONS
+
+impl Display for RunningError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return Display::fmt(self.inner(), formatter);
+    }
+}
+
+impl std::error::Error for RunningError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        return self.inner().source();
+    }
+}
+
+impl ErrorCompat for RunningError {
+    fn backtrace(&self) -> Option<&Backtrace> {
+        return match self {
+            RunningError::Callable(callable_error) => ErrorCompat::backtrace(callable_error),
+            RunningError::LoggingFormat(logging_format_error) => {
+                ErrorCompat::backtrace(logging_format_error)
+            }
+            RunningError::Command(command_error) => ErrorCompat::backtrace(command_error),
+            RunningError::Job(job_error) => ErrorCompat::backtrace(job_error),
+            RunningError::Retry(retry_error) => ErrorCompat::backtrace(retry_error),
+            RunningError::Executor(executor_error) => ErrorCompat::backtrace(executor_error),
+            #[cfg(feature = "signal_support")]
+            RunningError::Signal(signal_error) => ErrorCompat::backtrace(signal_error),
+            #[cfg(feature = "manifest_support")]
+            RunningError::Manifest(manifest_error) => ErrorCompat::backtrace(manifest_error),
+            RunningError::Io(_) => None,
+        };
+    }
+}
+
+impl Display for ErrorCode {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return formatter.write_str(self.as_str());
+    }
+}
+
+impl From<std::io::Error> for RunningError {
+    fn from(io_error: std::io::Error) -> Self {
+        return RunningError::Io(io_error);
+    }
+}
+
+// TESTS
+
+#[cfg(test)]
+mod tests {
+
+    // IMPORTS
+
+    use super::{ErrorCode, RunningError};
+    use crate::callable::{Callable, CallableError};
+    use crate::instruction::Command;
+    use crate::RunAndReturn;
+    use snafu::ErrorCompat;
+    use std::error::Error;
+
+    // FUNCTIONS
+
+    fn assert_send_sync<T: Send + Sync + 'static>() {}
+
+    // TESTS
+
+    #[test]
+    fn codes_and_sources() {
+        assert_send_sync::<RunningError>();
+
+        let error = Callable::new(|text: &'static str| text.parse::<i32>())
+            .args(("forty-two",))
+            .fallible()
+            .run_and_return()
+            .unwrap_err();
+        assert_eq!(error.code(), ErrorCode::CallableFailed);
+        assert_eq!(error.code().to_string(), "callable.failed");
+        assert!(error
+            .to_string()
+            .starts_with("Callable failed: invalid digit"));
+        let source = error.source().unwrap();
+        assert!(source.downcast_ref::<std::num::ParseIntError>().is_some());
+        assert!(matches!(
+            error,
+            RunningError::Callable(CallableError::CallableFailed { .. })
+        ));
+        assert!(ErrorCompat::backtrace(&error).is_some());
+        assert!(!error.is_cancellation());
+
+        let error = Command::new("sh")
+            .arg("-c")
+            .arg("exit 2")
+            .run_and_return()
+            .unwrap_err();
+        assert_eq!(error.code().as_str(), "command.failed");
+
+        let error = RunningError::from(std::io::Error::other("disk full"));
+        assert_eq!(error.code(), ErrorCode::Io);
+        assert_eq!(error.to_string(), "disk full");
+    }
+
+    #[test]
+    #[cfg(feature = "backtrace_support")]
+    fn captured_backtraces() {
+        let error = Command::new("sh")
+            .arg("-c")
+            .arg("exit 2")
+            .run_and_return()
+            .unwrap_err();
+        let backtrace = ErrorCompat::backtrace(&error).unwrap().to_string();
+        assert!(backtrace.lines().count() > 1);
+        assert!(backtrace.contains("captured_backtraces"));
+    }
+}
@@ -0,0 +1,399 @@
+// region: IMPORTS
+
+use crate::{AsyncRun, Run};
+use crate::{Error, RunningError};
+use async_trait::async_trait;
+#[cfg(feature = "tokio_support")]
+use snafu::ResultExt;
+use snafu::{Backtrace, OptionExt, Snafu};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+
+// endregion: IMPORTS
+
+// region: ERRORS
+
+#[derive(Debug, Snafu)]
+pub enum ExecutorError {
+    #[snafu(display(
+        "Spawned task lost. It either panicked or was dropped before producing a result"
+    ))]
+    SpawnedTaskLost { backtrace: Backtrace },
+    #[snafu(display("Runnable missing. It was lost during a previous run that did not complete"))]
+    RunnableMissing { backtrace: Backtrace },
+    #[snafu(display("Executor could not be started: {}", source))]
+    ExecutorStartFailed {
+        source: std::io::Error,
+        backtrace: Backtrace,
+    },
+}
+
+impl From<ExecutorError> for Error {
+    fn from(executor_error: ExecutorError) -> Self {
+        RunningError::Executor(executor_error)
+    }
+}
+
+// endregion: ERRORS
+
+// region: EXECUTOR
+
+/// A boxed future that can be sent across threads
+pub type BoxFuture<T> = Pin<Box<dyn Future<Output = T> + Send + 'static>>;
+
+/// A handle to a task spawned on an [Executor]. Awaiting it yields the output
+/// of the task, or an error if the task was lost
+pub struct JoinHandle<T> {
+    inner: BoxFuture<Result<T, Error>>,
+}
+
+impl<T> JoinHandle<T> {
+    /// Wraps a future that resolves to the output of a spawned task
+    pub fn new<F>(future: F) -> Self
+    where
+        F: Future<Output = Result<T, Error>> + Send + 'static,
+    {
+        return JoinHandle {
+            inner: Box::pin(future),
+        };
+    }
+}
+
+impl<T> Future for JoinHandle<T> {
+    type Output = Result<T, Error>;
+
+    fn poll(mut self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<Self::Output> {
+        return self.inner.as_mut().poll(context);
+    }
+}
+
+/// A trait that abstracts over async runtimes. The async runners of this crate
+/// only depend on this trait, so that they can be used with any runtime that
+/// has an implementation of it
+pub trait Executor: Clone + Send + Sync + 'static {
+    /// Drives a future to completion on the current thread, and returns its
+    /// output
+    fn block_on<F: Future>(&self, future: F) -> F::Output;
+
+    /// Runs a future in the background
+    fn spawn<F>(&self, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static;
+
+    /// Runs a blocking closure where it does not hold up other futures
+    fn spawn_blocking<C, T>(&self, callback: C) -> JoinHandle<T>
+    where
+        C: FnOnce() -> T + Send + 'static,
+        T: Send + 'static;
+}
+
+// endregion: EXECUTOR
+
+// region: THREAD EXECUTOR
+
+/// The state shared between the two ends of a [oneshot] channel
+struct OneshotState<T> {
+    value: Option<T>,
+    closed: bool,
+    waker: Option<Waker>,
+}
+
+/// The sending end of a [oneshot] channel. Dropping it without sending closes
+/// the channel
+struct OneshotSender<T> {
+    state: Arc<Mutex<OneshotState<T>>>,
+}
+
+/// The receiving end of a [oneshot] channel
+struct OneshotReceiver<T> {
+    state: Arc<Mutex<OneshotState<T>>>,
+}
+
+/// Creates a channel that carries a single value from a thread to a future
+fn oneshot<T>() -> (OneshotSender<T>, OneshotReceiver<T>) {
+    let state = Arc::new(Mutex::new(OneshotState {
+        value: None,
+        closed: false,
+        waker: None,
+    }));
+    return (
+        OneshotSender {
+            state: state.clone(),
+        },
+        OneshotReceiver { state },
+    );
+}
+
+impl<T> OneshotSender<T> {
+    fn send(self, value: T) {
+        self.state.lock().unwrap().value = Some(value);
+    }
+}
+
+impl<T> Drop for OneshotSender<T> {
+    fn drop(&mut self) {
+        let mut state = self.state.lock().unwrap();
+        state.closed = true;
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl<T> Future for OneshotReceiver<T> {
+    type Output = Result<T, Error>;
+
+    fn poll(self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(value) = state.value.take() {
+            return Poll::Ready(Ok(value));
+        }
+        if state.closed {
+            return Poll::Ready(SpawnedTaskLost.fail().map_err(Error::from));
+        }
+        state.waker = Some(context.waker().clone());
+        return Poll::Pending;
+    }
+}
+
+/// Wakes a future by unparking the thread that is blocked on it
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// A minimal executor that needs no async runtime. It blocks the calling
+/// thread on futures, and gives every spawned task a thread of its own
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ThreadExecutor;
+
+impl ThreadExecutor {
+    /// Creates a new thread executor
+    pub fn new() -> Self {
+        return ThreadExecutor;
+    }
+}
+
+impl Executor for ThreadExecutor {
+    fn block_on<F: Future>(&self, future: F) -> F::Output {
+        let mut future = Box::pin(future);
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut context = Context::from_waker(&waker);
+        loop {
+            match future.as_mut().poll(&mut context) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+
+    fn spawn<F>(&self, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let executor = *self;
+        return self.spawn_blocking(move || executor.block_on(future));
+    }
+
+    fn spawn_blocking<C, T>(&self, callback: C) -> JoinHandle<T>
+    where
+        C: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (sender, receiver) = oneshot();
+        thread::spawn(move || sender.send(callback()));
+        return JoinHandle::new(receiver);
+    }
+}
+
+// endregion: THREAD EXECUTOR
+
+// region: TOKIO EXECUTOR
+
+/// An executor backed by a tokio runtime
+#[cfg(feature = "tokio_support")]
+#[derive(Debug, Clone)]
+pub struct TokioExecutor {
+    runtime: Arc<tokio::runtime::Runtime>,
+}
+
+#[cfg(feature = "tokio_support")]
+impl TokioExecutor {
+    /// Creates an executor with a new multi-threaded tokio runtime
+    pub fn new() -> Result<Self, Error> {
+        let runtime = tokio::runtime::Runtime::new().context(ExecutorStartFailed)?;
+        return Ok(TokioExecutor::with_runtime(Arc::new(runtime)));
+    }
+
+    /// Creates an executor that shares an existing tokio runtime
+    pub fn with_runtime(runtime: Arc<tokio::runtime::Runtime>) -> Self {
+        return TokioExecutor { runtime };
+    }
+}
+
+#[cfg(feature = "tokio_support")]
+impl Executor for TokioExecutor {
+    fn block_on<F: Future>(&self, future: F) -> F::Output {
+        return self.runtime.block_on(future);
+    }
+
+    fn spawn<F>(&self, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let handle = self.runtime.spawn(future);
+        return JoinHandle::new(async move {
+            return handle
+                .await
+                .ok()
+                .context(SpawnedTaskLost)
+                .map_err(Error::from);
+        });
+    }
+
+    fn spawn_blocking<C, T>(&self, callback: C) -> JoinHandle<T>
+    where
+        C: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let handle = self.runtime.spawn_blocking(callback);
+        return JoinHandle::new(async move {
+            return handle
+                .await
+                .ok()
+                .context(SpawnedTaskLost)
+                .map_err(Error::from);
+        });
+    }
+}
+
+// endregion: TOKIO EXECUTOR
+
+// region: ASYNC RUNNER
+
+/// Runs a blocking runnable asynchronously. Each run moves the runnable onto a
+/// blocking thread of the given [Executor], and moves it back when done
+pub struct AsyncRunner<T, E> {
+    runnable: Option<T>,
+    executor: E,
+}
+
+impl<T, E> AsyncRunner<T, E>
+where
+    E: Executor,
+{
+    /// Creates a new async runner for the given runnable
+    pub fn new(runnable: T, executor: E) -> Self {
+        return AsyncRunner {
+            runnable: Some(runnable),
+            executor,
+        };
+    }
+
+    /// Returns the wrapped runnable, if it was not lost in a panicking run
+    pub fn into_inner(self) -> Option<T> {
+        return self.runnable;
+    }
+}
+
+#[async_trait]
+impl<T, E> AsyncRun for AsyncRunner<T, E>
+where
+    T: Run + Send + 'static,
+    E: Executor,
+{
+    async fn async_run(&mut self) -> Result<(), Error> {
+        let mut runnable = self.runnable.take().context(RunnableMissing)?;
+        let (runnable, result) = self
+            .executor
+            .spawn_blocking(move || {
+                let result = runnable.run();
+                (runnable, result)
+            })
+            .await?;
+        self.runnable = Some(runnable);
+        return result;
+    }
+}
+
+// endregion: ASYNC RUNNER
+
+// region: TESTS
+
+#[cfg(test)]
+mod tests {
+
+    // IMPORTS
+
+    #[cfg(feature = "tokio_support")]
+    use super::TokioExecutor;
+    use super::{AsyncRunner, Executor, ThreadExecutor};
+    use crate::callable::Callable;
+    use crate::AsyncRun;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    // TESTS
+
+    #[test]
+    fn thread_executor_spawn() {
+        let executor = ThreadExecutor::new();
+        let output = executor.block_on(async {
+            let first = executor.spawn(async { 2 + 2 });
+            let second = executor.spawn_blocking(|| 3 * 3);
+            first.await.unwrap() + second.await.unwrap()
+        });
+        assert_eq!(output, 13);
+    }
+
+    #[test]
+    fn thread_executor_lost_task() {
+        let executor = ThreadExecutor::new();
+        let handle = executor.spawn_blocking(|| -> usize { panic!("Panicking test...") });
+        assert!(executor.block_on(handle).is_err());
+    }
+
+    #[cfg(feature = "tokio_support")]
+    #[test]
+    fn tokio_executor_spawn() {
+        let executor = TokioExecutor::new().unwrap();
+        let output = executor.block_on(async {
+            let first = executor.spawn(async { 2 + 2 });
+            let second = executor.spawn_blocking(|| 3 * 3);
+            first.await.unwrap() + second.await.unwrap()
+        });
+        assert_eq!(output, 13);
+    }
+
+    #[cfg(feature = "tokio_support")]
+    #[test]
+    fn tokio_executor_lost_task() {
+        let executor = TokioExecutor::new().unwrap();
+        let handle = executor.spawn_blocking(|| -> usize { panic!("Panicking test...") });
+        assert!(executor.block_on(handle).is_err());
+    }
+
+    #[test]
+    fn async_runner() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let counter_clone = counter.clone();
+        let callable = Callable::new(move || counter_clone.fetch_add(1, Ordering::SeqCst));
+        let executor = ThreadExecutor::new();
+        let mut runner = AsyncRunner::new(callable, executor);
+
+        executor.block_on(runner.async_run()).unwrap();
+        assert_eq!(counter.load(Ordering::SeqCst), 1);
+        assert!(runner.into_inner().is_some());
+    }
+}
+
+// endregion: TESTS
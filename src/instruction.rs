@@ -1,153 +1,693 @@
 // IMPORTS
-use crate::Run;
+use crate::cancellation::CancellationToken;
+use crate::observer::OutputStream;
+use crate::runnable::TaskContext;
+#[cfg(feature = "signal_support")]
+use crate::signal;
+#[cfg(feature = "tracing_support")]
+use crate::trace::{self, TracedRun};
+use crate::{AcceptInput, Run, RunAndCallback, RunAndReturn};
+use crate::{Error, RunningError};
+#[cfg(feature = "serde_support")]
+use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
+use snafu::{Backtrace, ResultExt, Snafu};
+#[cfg(feature = "serde_support")]
+use std::collections::BTreeMap;
 use std::{
-    ffi::OsStr,
-    ops::{Deref, DerefMut},
+    ffi::{OsStr, OsString},
+    io::{BufRead, BufReader, Read, Write},
+    path::{Path, PathBuf},
+    process::{Child, ExitStatus, Output, Stdio},
+    thread,
+    time::Duration,
 };
 
-// #[cfg(feature = "async")]
-// use {async_trait::async_trait, tokio::process::Child};
-
-// #[cfg(not(feature = "async"))]
-// use std::process::Child;
-
-// // TODO
-// /*
-// - Logging
-// - Async
-// - Pipe method and operator
-// */
-
-// // STRUCT DECLARATIONS
-
-// #[cfg(not(feature = "async"))]
-// pub struct Command {
-//     inner_command: std::process::Command,
-//     result: Option<std::io::Result<Child>>,
-// }
-
-// #[cfg(feature = "async")]
-// pub struct Command {
-//     inner_command: tokio::process::Command,
-//     result: Option<std::io::Result<Child>>,
-// }
-
-// // STRUCT IMPLEMENTATIONS
-
-// impl Command {
-//     pub fn new<S: AsRef<OsStr>>(program: S) -> Command {
-//         Command {
-//             result: None,
-
-//             #[cfg(feature = "async")]
-//             inner_command: tokio::process::Command::new(program),
-
-//             #[cfg(not(feature = "async"))]
-//             inner_command: std::process::Command::new(program),
-//         }
-//     }
-
-//     pub fn arg<S: AsRef<OsStr>>(&mut self, argument: S) -> &mut Command {
-//         self.inner_command.arg(argument);
-//         self
-//     }
-
-//     pub fn args<I, S>(&mut self, arguments: I) -> &mut Command
-//     where
-//         I: IntoIterator<Item = S>,
-//         S: AsRef<OsStr>,
-//     {
-//         self.inner_command.args(arguments);
-//         self
-//     }
-// }
-
-// // TRAIT IMPLEMENTATIONS
-
-// impl Deref for Command {
-//     #[cfg(feature = "async")]
-//     type Target = tokio::process::Command;
-
-//     #[cfg(not(feature = "async"))]
-//     type Target = std::process::Command;
-
-//     fn deref(&self) -> &Self::Target {
-//         &self.inner_command
-//     }
-// }
-
-// impl DerefMut for Command {
-//     fn deref_mut(&mut self) -> &mut Self::Target {
-//         &mut self.inner_command
-//     }
-// }
-
-// #[cfg(feature = "async")]
-// #[async_trait]
-// impl Runnable for Command {
-//     async fn run(&mut self) {
-//         self.result = Some(self.inner_command.spawn().await);
-//     }
-// }
-
-// #[cfg(not(feature = "async"))]
-// impl Run for Command {
-//     fn run(&mut self) {
-//         self.result = Some(self.inner_command.spawn());
-//     }
-// }
+// CONSTANTS
+
+/// How often a command that can be cancelled checks whether it should be
+/// killed
+const KILL_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+// ERRORS
+
+#[derive(Debug, Snafu)]
+pub enum CommandError {
+    #[snafu(display("Command `{}` could not be run: {}", program, source))]
+    CommandStartFailed {
+        program: String,
+        source: std::io::Error,
+        backtrace: Backtrace,
+    },
+    #[snafu(display("Command `{}` exited unsuccessfully ({})", program, status))]
+    CommandFailed {
+        program: String,
+        status: ExitStatus,
+        output: Output,
+        backtrace: Backtrace,
+    },
+    #[snafu(display("Command `{}` was killed because it was cancelled", program))]
+    CommandKilled {
+        program: String,
+        backtrace: Backtrace,
+    },
+}
+
+impl CommandError {
+    /// The exit code of the command, if it ran to completion and was not
+    /// terminated by a signal
+    pub fn exit_code(&self) -> Option<i32> {
+        match self {
+            CommandError::CommandFailed { status, .. } => status.code(),
+            _ => None,
+        }
+    }
+}
+
+impl From<CommandError> for Error {
+    fn from(command_error: CommandError) -> Self {
+        RunningError::Command(command_error)
+    }
+}
+
+// STRUCT DECLARATIONS
+
+/// A program, script, or operating system command to be run as a child
+/// process. Its standard output and standard error are captured
+#[derive(Debug, Clone)]
+pub struct Command {
+    program: OsString,
+    arguments: Vec<OsString>,
+    environment: Vec<(OsString, OsString)>,
+    current_directory: Option<PathBuf>,
+    stdin: Option<Vec<u8>>,
+    allowed_exit_codes: Vec<i32>, // besides 0, which is always a success
+    cancellation_token: Option<CancellationToken>,
+}
+
+/// Inputs supplied to a [Command] just before it runs: arguments to append,
+/// and bytes to write to its standard input
+#[derive(Debug, Clone, Default)]
+pub struct CommandInput {
+    arguments: Vec<OsString>,
+    stdin: Option<Vec<u8>>,
+}
+
+/// What a [Command] wrote and how it exited, in a form that can be logged or
+/// serialized. Made from the [Output] of a run, with both outputs decoded as
+/// UTF-8, lossily. With `serde_support`, it is serialized as
+/// `{"exit_code": 0, "success": true, "stdout": "...", "stderr": "..."}`,
+/// where the exit code is null if the command was terminated by a signal
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct CommandOutput {
+    pub exit_code: Option<i32>,
+    pub success: bool,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// The serialized form of a [Command]. Programs, arguments, environment
+/// variables and the standard input are strings, so commands with other
+/// contents cannot be serialized
+#[cfg(feature = "serde_support")]
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct CommandFormat {
+    pub(crate) program: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) args: Vec<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) env: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) cwd: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) stdin: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) allowed_exit_codes: Vec<i32>,
+}
+
+// STRUCT IMPLEMENTATIONS
+
+impl CommandInput {
+    /// Creates an empty command input
+    pub fn new() -> Self {
+        return CommandInput::default();
+    }
+
+    /// Appends an argument
+    pub fn arg<S: AsRef<OsStr>>(mut self, argument: S) -> Self {
+        self.arguments.push(argument.as_ref().to_os_string());
+        return self;
+    }
+
+    /// Appends several arguments
+    pub fn args<I, S>(mut self, arguments: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        self.arguments.extend(
+            arguments
+                .into_iter()
+                .map(|argument| argument.as_ref().to_os_string()),
+        );
+        return self;
+    }
+
+    /// Sets the bytes to be written to the standard input
+    pub fn stdin<B: Into<Vec<u8>>>(mut self, bytes: B) -> Self {
+        self.stdin = Some(bytes.into());
+        return self;
+    }
+}
+
+impl Command {
+    /// Creates a new command for the given program, with no arguments
+    pub fn new<S: AsRef<OsStr>>(program: S) -> Command {
+        return Command {
+            program: program.as_ref().to_os_string(),
+            arguments: Vec::new(),
+            environment: Vec::new(),
+            current_directory: None,
+            stdin: None,
+            allowed_exit_codes: Vec::new(),
+            cancellation_token: None,
+        };
+    }
+
+    /// Appends an argument to the command
+    pub fn arg<S: AsRef<OsStr>>(mut self, argument: S) -> Command {
+        self.arguments.push(argument.as_ref().to_os_string());
+        return self;
+    }
+
+    /// Appends several arguments to the command
+    pub fn args<I, S>(mut self, arguments: I) -> Command
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        self.arguments.extend(
+            arguments
+                .into_iter()
+                .map(|argument| argument.as_ref().to_os_string()),
+        );
+        return self;
+    }
+
+    /// Sets an environment variable for the command
+    pub fn env<K: AsRef<OsStr>, V: AsRef<OsStr>>(mut self, key: K, value: V) -> Command {
+        self.environment
+            .push((key.as_ref().to_os_string(), value.as_ref().to_os_string()));
+        return self;
+    }
+
+    /// Sets the working directory of the command
+    pub fn current_dir<P: AsRef<Path>>(mut self, directory: P) -> Command {
+        self.current_directory = Some(directory.as_ref().to_path_buf());
+        return self;
+    }
+
+    /// Sets the bytes to be written to the standard input of the command
+    pub fn stdin<B: Into<Vec<u8>>>(mut self, bytes: B) -> Command {
+        self.stdin = Some(bytes.into());
+        return self;
+    }
+
+    /// Treats the given exit codes as successes too, besides 0. For example,
+    /// `grep` exits with 1 when it finds no match
+    pub fn allowed_exit_codes(mut self, exit_codes: &[i32]) -> Command {
+        self.allowed_exit_codes.extend_from_slice(exit_codes);
+        return self;
+    }
+
+    /// Sets a token that kills the command when it is cancelled. Within a job,
+    /// the command is also killed when the job is cancelled
+    pub fn cancellation_token(mut self, token: CancellationToken) -> Command {
+        self.cancellation_token = Some(token);
+        return self;
+    }
+
+    /// The program of the command, as a string for messages and logs
+    pub fn program(&self) -> String {
+        return self.program.to_string_lossy().into_owned();
+    }
+
+    /// Builds the standard library command that is spawned on each run. Within
+    /// a job that handles signals, the command leads a process group of its
+    /// own, so that signals from the terminal are left to the job to forward
+    fn inner_command(&self, context: Option<&TaskContext>) -> std::process::Command {
+        let mut inner_command = std::process::Command::new(&self.program);
+        inner_command
+            .args(&self.arguments)
+            .envs(self.environment.iter().map(|(key, value)| (key, value)))
+            .stdin(match self.stdin {
+                Some(_) => Stdio::piped(),
+                None => Stdio::null(),
+            })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        if let Some(directory) = self.current_directory.as_ref() {
+            inner_command.current_dir(directory);
+        }
+        #[cfg(feature = "signal_support")]
+        if context.is_some_and(|context| context.handles_signals()) {
+            use std::os::unix::process::CommandExt;
+            inner_command.process_group(0);
+        }
+        #[cfg(not(feature = "signal_support"))]
+        let _ = context;
+        return inner_command;
+    }
+
+    /// Spawns the command, and waits for it to exit. With tracing, its output
+    /// lines are recorded in the given span
+    fn run_child(
+        &mut self,
+        #[cfg(feature = "tracing_support")] span: &tracing::Span,
+    ) -> Result<Output, Error> {
+        let program = self.program();
+        let context = TaskContext::current();
+        let mut child = self
+            .inner_command(context.as_ref())
+            .spawn()
+            .context(CommandStartFailed { program: &program })?;
+        // Feed the standard input and drain the outputs from other threads, so
+        // that a child blocked on a full pipe cannot deadlock against us
+        let stdin_writer = match (self.stdin.clone(), child.stdin.take()) {
+            (Some(bytes), Some(mut child_stdin)) => {
+                Some(thread::spawn(move || child_stdin.write_all(&bytes)))
+            }
+            _ => None,
+        };
+        let observing_context = context.clone().filter(|context| context.observes_output());
+        let stdout_reader = child.stdout.take().map(|pipe| {
+            read_to_end(
+                pipe,
+                OutputStream::Stdout,
+                observing_context.clone(),
+                #[cfg(feature = "tracing_support")]
+                span.clone(),
+            )
+        });
+        let stderr_reader = child.stderr.take().map(|pipe| {
+            read_to_end(
+                pipe,
+                OutputStream::Stderr,
+                observing_context,
+                #[cfg(feature = "tracing_support")]
+                span.clone(),
+            )
+        });
+
+        let job_token = context.as_ref().map(|context| context.cancellation_token());
+        let status = match (self.cancellation_token.as_ref(), job_token) {
+            (None, None) => {
+                child
+                    .wait()
+                    .context(CommandStartFailed { program: &program })?
+            }
+            (command_token, job_token) => {
+                let token = CancellationToken::child_of(command_token.into_iter().chain(job_token));
+                wait_or_kill(&mut child, &token, context.as_ref(), &program)?
+            }
+        };
+        if let Some(stdin_writer) = stdin_writer {
+            // A child that exits without reading all of its input is not an error
+            let _ = stdin_writer.join();
+        }
+        let output = Output {
+            status,
+            stdout: join_reader(stdout_reader),
+            stderr: join_reader(stderr_reader),
+        };
+        let allowed = output
+            .status
+            .code()
+            .is_some_and(|code| self.allowed_exit_codes.contains(&code));
+        if !output.status.success() && !allowed {
+            return CommandFailed {
+                program,
+                status: output.status,
+                output,
+            }
+            .fail()
+            .map_err(Error::from);
+        }
+        return Ok(output);
+    }
+}
+
+#[cfg(feature = "serde_support")]
+impl CommandFormat {
+    /// The serialized form of a command, or a message saying which part of it
+    /// is not a string
+    pub(crate) fn from_command(command: &Command) -> Result<Self, String> {
+        let string = |value: &OsStr, part: &str| -> Result<String, String> {
+            return value.to_str().map(str::to_string).ok_or_else(|| {
+                format!(
+                    "the {} of command `{}` is not valid UTF-8",
+                    part,
+                    command.program()
+                )
+            });
+        };
+        let stdin = match command.stdin.as_ref() {
+            Some(bytes) => {
+                Some(String::from_utf8(bytes.clone()).map_err(|_error| {
+                    format!(
+                        "the stdin of command `{}` is not valid UTF-8",
+                        command.program()
+                    )
+                })?)
+            }
+            None => None,
+        };
+        return Ok(CommandFormat {
+            program: string(&command.program, "program")?,
+            args: command
+                .arguments
+                .iter()
+                .map(|argument| string(argument, "arguments"))
+                .collect::<Result<_, _>>()?,
+            env: command
+                .environment
+                .iter()
+                .map(|(key, value)| {
+                    Ok((string(key, "environment")?, string(value, "environment")?))
+                })
+                .collect::<Result<_, String>>()?,
+            cwd: command.current_directory.clone(),
+            stdin,
+            allowed_exit_codes: command.allowed_exit_codes.clone(),
+        });
+    }
+
+    /// The command that this is the serialized form of
+    pub(crate) fn into_command(self) -> Command {
+        let mut command = Command::new(self.program).args(self.args);
+        for (key, value) in self.env {
+            command = command.env(key, value);
+        }
+        if let Some(directory) = self.cwd {
+            command = command.current_dir(directory);
+        }
+        if let Some(stdin) = self.stdin {
+            command = command.stdin(stdin);
+        }
+        return command.allowed_exit_codes(&self.allowed_exit_codes);
+    }
+}
+
+// FUNCTIONS
+
+/// Reads a pipe of a child process to its end on another thread. If a job
+/// observes the output of the command, each line is also passed to it as soon
+/// as it is read. With tracing, each line is also recorded as an event in the
+/// span of the command
+fn read_to_end<R: Read + Send + 'static>(
+    pipe: R,
+    stream: OutputStream,
+    context: Option<TaskContext>,
+    #[cfg(feature = "tracing_support")] span: tracing::Span,
+) -> thread::JoinHandle<Vec<u8>> {
+    return thread::spawn(move || {
+        let mut reader = BufReader::new(pipe);
+        let mut buffer = Vec::new();
+        loop {
+            let line_start = buffer.len();
+            match reader.read_until(b'\n', &mut buffer) {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {
+                    let line = String::from_utf8_lossy(&buffer[line_start..]);
+                    let line = line.trim_end_matches(&['\n', '\r'][..]);
+                    if let Some(context) = context.as_ref() {
+                        context.output_line(stream, line);
+                    }
+                    #[cfg(feature = "tracing_support")]
+                    trace::output_line(&span, stream, line);
+                }
+            }
+        }
+        buffer
+    });
+}
+
+/// Collects the bytes read by [read_to_end]
+fn join_reader(reader: Option<thread::JoinHandle<Vec<u8>>>) -> Vec<u8> {
+    return reader
+        .and_then(|reader| reader.join().ok())
+        .unwrap_or_default();
+}
+
+/// Waits for a child process to exit, but kills it if the token is cancelled
+/// in the meantime. Within a job that handles signals, the signal that
+/// interrupts the job is forwarded to the process group of the child, and the
+/// whole group is killed on cancellation or on a second signal
+fn wait_or_kill(
+    child: &mut Child,
+    token: &CancellationToken,
+    context: Option<&TaskContext>,
+    program: &str,
+) -> Result<ExitStatus, Error> {
+    #[cfg(feature = "signal_support")]
+    let process_group = context
+        .filter(|context| context.handles_signals())
+        .map(|_context| child.id());
+    #[cfg(feature = "signal_support")]
+    let _tracked_process_group = process_group
+        .zip(context)
+        .and_then(|(process_group, context)| context.track_process_group(process_group));
+    #[cfg(feature = "signal_support")]
+    let mut signal_forwarded = false;
+    #[cfg(not(feature = "signal_support"))]
+    let _ = context;
+    loop {
+        if let Some(status) = child.try_wait().context(CommandStartFailed { program })? {
+            return Ok(status);
+        }
+        #[cfg(feature = "signal_support")]
+        if let (Some(process_group), false) = (process_group, signal_forwarded) {
+            if let Some(signal) = context.and_then(|context| context.interruption_signal()) {
+                signal::signal_process_group(process_group, signal);
+                signal_forwarded = true;
+            }
+        }
+        if token.is_cancelled() {
+            #[cfg(feature = "signal_support")]
+            if let Some(process_group) = process_group {
+                signal::kill_process_group(process_group);
+            }
+            let _ = child.kill();
+            let _ = child.wait();
+            return CommandKilled { program }.fail().map_err(Error::from);
+        }
+        thread::sleep(KILL_POLL_INTERVAL);
+    }
+}
+
+// TRAIT IMPLEMENTATIONS
+
+impl RunAndReturn for Command {
+    type ReturnType = Output;
+
+    #[cfg(feature = "tracing_support")]
+    fn run_and_return(&mut self) -> Result<Self::ReturnType, Error> {
+        let arguments: Vec<_> = self
+            .arguments
+            .iter()
+            .map(|argument| argument.to_string_lossy())
+            .collect();
+        let traced_run =
+            TracedRun::start(trace::command_span(&self.program(), &arguments.join(" ")));
+        let result = traced_run
+            .span()
+            .in_scope(|| self.run_child(traced_run.span()));
+        traced_run.complete_result(&result);
+        return result;
+    }
+
+    #[cfg(not(feature = "tracing_support"))]
+    fn run_and_return(&mut self) -> Result<Self::ReturnType, Error> {
+        return self.run_child();
+    }
+}
+
+impl AcceptInput for Command {
+    type Input = CommandInput;
+
+    /// Appends the arguments of the input, and replaces the standard input if
+    /// the input has one
+    fn accept_input(&mut self, input: Self::Input) {
+        self.arguments.extend(input.arguments);
+        if input.stdin.is_some() {
+            self.stdin = input.stdin;
+        }
+    }
+}
+
+impl Run for Command {
+    fn run(&mut self) -> Result<(), Error> {
+        return self.run_and_return().map(|_output| ());
+    }
+}
+
+impl RunAndCallback for Command {
+    fn run_and_then<C: FnOnce(Self::ReturnType)>(&mut self, callback: C) -> Result<(), Error> {
+        return self.run_and_return().map(callback);
+    }
+}
+
+impl From<Output> for CommandOutput {
+    fn from(output: Output) -> Self {
+        return CommandOutput {
+            exit_code: output.status.code(),
+            success: output.status.success(),
+            stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        };
+    }
+}
+
+/// Commands are serialized as
+/// `{"program": "...", "args": [...], "env": {...}, "cwd": "...", "stdin":
+/// "...", "allowed_exit_codes": [...]}`, where every field but the program may
+/// be left out. The cancellation token of a command is not serialized
+#[cfg(feature = "serde_support")]
+impl Serialize for Command {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        return CommandFormat::from_command(self)
+            .map_err(ser::Error::custom)?
+            .serialize(serializer);
+    }
+}
+
+#[cfg(feature = "serde_support")]
+impl<'de> Deserialize<'de> for Command {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let format = CommandFormat::deserialize(deserializer)?;
+        if format.program.is_empty() {
+            return Err(de::Error::custom(
+                "the program of a command cannot be empty",
+            ));
+        }
+        return Ok(format.into_command());
+    }
+}
 
 // TESTS
 
-// #[cfg(test)]
-// mod tests {
-
-//     // IMPORTS
-
-//     #[cfg(feature = "async")]
-//     use tokio::runtime::Runtime;
-
-//     #[cfg(feature = "logging")]
-//     use crate::tests::setup_logging;
-
-//     // TESTS
-
-//     #[test]
-//     fn echo() {
-//         #[cfg(feature = "logging")]
-//         setup_logging(log::LevelFilter::Debug);
-
-//         #[cfg(feature = "async")]
-//         {
-//             let runtime = Runtime::new().unwrap();
-//             let output: String = String::from_utf8(
-//                 runtime
-//                     .block_on(async {
-//                         super::Command::new("echo")
-//                             .arg("Hello")
-//                             .arg("World")
-//                             .output()
-//                             .await
-//                     })
-//                     .expect("Unable to run...")
-//                     .stdout,
-//             )
-//             .expect("Unable to convert from utf-8 to String");
-//             assert_eq!(output, String::from("Hello World\n"));
-//         };
-
-//         #[cfg(not(feature = "async"))]
-//         {
-//             let output: String = String::from_utf8(
-//                 super::Command::new("echo")
-//                     .arg("Hello")
-//                     .arg("World")
-//                     .output()
-//                     .expect("Unable to run...")
-//                     .stdout,
-//             )
-//             .expect("Unable to convert from utf-8 to String");
-//             assert_eq!(output, String::from("Hello World\n"));
-//         };
-//     }
-// }
+#[cfg(test)]
+mod tests {
+
+    // IMPORTS
+
+    use super::{Command, CommandError, CommandOutput};
+    use crate::cancellation::CancellationToken;
+    use crate::{RunAndReturn, RunningError};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    // TESTS
+
+    #[test]
+    fn echo() {
+        let output = Command::new("echo")
+            .arg("Hello")
+            .arg("World")
+            .run_and_return()
+            .expect("Unable to run...");
+        let output =
+            String::from_utf8(output.stdout).expect("Unable to convert from utf-8 to String");
+        assert_eq!(output, String::from("Hello World\n"));
+    }
+
+    #[test]
+    fn stdin() {
+        let output = Command::new("cat")
+            .stdin("Hello World")
+            .run_and_return()
+            .expect("Unable to run...");
+        assert_eq!(output.stdout, b"Hello World");
+    }
+
+    #[test]
+    fn exit_code() {
+        let error = Command::new("sh")
+            .args(["-c", "exit 3"])
+            .run_and_return()
+            .unwrap_err();
+        match error {
+            RunningError::Command(command_error) => assert_eq!(command_error.exit_code(), Some(3)),
+            _ => panic!("Expected the command to fail, but got: {}", error),
+        }
+
+        let output = Command::new("sh")
+            .args(["-c", "exit 3"])
+            .allowed_exit_codes(&[1, 3])
+            .run_and_return()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(3));
+    }
+
+    #[test]
+    fn cancellation() {
+        let token = CancellationToken::new();
+        let cancelling_token = token.clone();
+        let canceller = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            cancelling_token.cancel();
+        });
+
+        let start = Instant::now();
+        let error = Command::new("sleep")
+            .arg("10")
+            .cancellation_token(token)
+            .run_and_return()
+            .unwrap_err();
+        canceller.join().unwrap();
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert!(matches!(
+            error,
+            RunningError::Command(CommandError::CommandKilled { .. })
+        ));
+    }
+
+    #[test]
+    fn command_output() {
+        let output = Command::new("sh")
+            .args(["-c", "echo out; echo err >&2"])
+            .run_and_return()
+            .unwrap();
+        let output = CommandOutput::from(output);
+        assert_eq!(output.exit_code, Some(0));
+        assert!(output.success);
+        assert_eq!(output.stdout, "out\n");
+        assert_eq!(output.stderr, "err\n");
+    }
+
+    #[cfg(feature = "serde_support")]
+    #[test]
+    fn serde() {
+        let command = Command::new("sh")
+            .args(["-c", "echo $GREETING; cat"])
+            .env("GREETING", "Hello")
+            .current_dir("/")
+            .stdin("World");
+        let json = serde_json::to_string(&command).unwrap();
+        assert_eq!(
+            json,
+            r#"{"program":"sh","args":["-c","echo $GREETING; cat"],"env":{"GREETING":"Hello"},"cwd":"/","stdin":"World"}"#
+        );
+        let mut command: Command = serde_json::from_str(&json).unwrap();
+        let output = CommandOutput::from(command.run_and_return().unwrap());
+        assert_eq!(output.stdout, "Hello\nWorld");
+        assert_eq!(
+            serde_json::to_string(&output).unwrap(),
+            r#"{"exit_code":0,"success":true,"stdout":"Hello\nWorld","stderr":""}"#
+        );
+
+        assert!(serde_json::from_str::<Command>(r#"{"program":"ls","arguments":[]}"#).is_err());
+        assert!(serde_json::from_str::<Command>(r#"{"program":""}"#).is_err());
+    }
+}
@@ -1,61 +1,260 @@
-#![feature(unboxed_closures)] // to switch from parenthetical notation to generics for `Fn*` traits
-#![feature(fn_traits)] // to use `call_once` and `call` methods on Fn* traits
-#![feature(trait_alias)] // to give simple names for sets of traits
-#![feature(specialization)] // for specialization of trait implementations
-#![feature(stmt_expr_attributes)] // for selective evaluation of expressions based on attributes
-
 //! `running` is a library for running *callables* (functions and closures), and
 //! *external commands* (programs, scripts, and operating system commands), or a
 //! set of them with optional live logging and optional asynchrony.
 
+#![allow(clippy::needless_return)] // explicit returns are the style of this crate
+
+extern crate self as running; // for the macros of `running_macros`, which expand to paths in `running`
+
 use async_trait::async_trait;
-use snafu::ResultExt;
-use std::fmt::{Debug, Display};
+use std::any::Any;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::ffi::{OsStr, OsString};
+use std::fmt::Debug;
+use std::path::{Path, PathBuf};
+use std::process::{ExitStatus, Output};
+use std::rc::Rc;
 use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
 
 pub mod callable; // for types and traits pertaining to the execution of functions and closures
+pub mod cancellation; // for cooperatively cancelling running jobs, commands and callables
+pub mod error; // for the error type of the crate, which wraps the errors of each module
+pub mod executor; // for abstracting over the async runtimes that drive asynchronous runs
 pub mod instruction; /* for types and traits pertaining to the execution of programs, scripts, and
-                  * operating system commands */
+                      * operating system commands */
+#[cfg(feature = "manifest_support")]
+pub mod manifest; // for declaring jobs of commands in TOML, YAML or JSON files
+pub mod observer; // for observing the lifecycle events of jobs and their tasks
+pub mod retry; // for retrying runnables that fail transiently, with backoff
 pub mod runnable; // for types and traits pertaining to the execution of a batch of callables and
-              // commands
+                  // commands
+#[cfg(feature = "signal_support")]
+pub mod signal; // for interrupting jobs gracefully on SIGINT and SIGTERM
+#[cfg(feature = "serde_support")]
+pub mod spec; // for serializable definitions of jobs of commands
+#[cfg(feature = "tracing_support")]
+mod trace; // for tracing the runs of jobs, tasks, callables and commands in spans
 
-pub trait ErrorTrait: std::error::Error + snafu::ErrorCompat {}
-impl<T> ErrorTrait for T where T: std::error::Error + snafu::ErrorCompat {}
-pub type Error = Box<dyn ErrorTrait>;
+pub use running_macros::task; // for declaring functions as named, logged tasks
+pub use running_macros::Task; // for declaring structs with a run method as named, logged tasks
+
+pub use error::{ErrorCode, RunningError};
+pub type Error = RunningError;
 
 static TASK_ID_GENERATOR: AtomicUsize = AtomicUsize::new(0); // initialize the unique task ID generator
 pub fn generate_task_id() -> usize {
     TASK_ID_GENERATOR.fetch_add(1, Ordering::Relaxed)
 }
 
+/// Renders a value for logs, like the arguments of a logged callable. It is
+/// implemented with [std::fmt::Display] for primitives, strings and errors,
+/// and with [Debug] for unit, tuples, options, results, collections and paths.
+/// Implement it for other types to log them. Unlike the blanket implementation
+/// it replaces, it is not implemented for every type: where a value of any
+/// type has to be rendered, use [represent!] instead
 pub trait Represent {
     fn represent(&self) -> String;
 }
 
-impl<T> Represent for T {
-    default fn represent(&self) -> String {
-        return String::new();
-    }
+/// Implements [Represent] for the given types with [std::fmt::Display]
+macro_rules! represent_with_display {
+    ($($represented:ty),*) => {
+        $(
+            impl Represent for $represented {
+                fn represent(&self) -> String {
+                    return format!("{}", self);
+                }
+            }
+        )*
+    };
+}
+
+/// Implements [Represent] for the given types with [Debug], when the given
+/// type parameters implement [Debug]
+macro_rules! represent_with_debug {
+    ($([$($parameter:ident),*] $represented:ty),*) => {
+        $(
+            impl<$($parameter: Debug),*> Represent for $represented {
+                fn represent(&self) -> String {
+                    return format!("{:?}", self);
+                }
+            }
+        )*
+    };
 }
 
-impl<T> Represent for T
-where
-    T: Debug,
-{
-    default fn represent(&self) -> String {
+represent_with_display!(
+    bool, char, f32, f64, i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize
+);
+represent_with_display!(str, String, ExitStatus, RunningError);
+represent_with_debug!([] (), [] Duration, [] Path, [] PathBuf, [] OsStr, [] OsString, [] Output);
+represent_with_debug!([T] Option<T>, [T, E] Result<T, E>, [T] [T], [T] Vec<T>, [T] VecDeque<T>);
+represent_with_debug!([T] BTreeSet<T>, [K, V] BTreeMap<K, V>, [T, S] HashSet<T, S>, [K, V, S] HashMap<K, V, S>);
+represent_with_debug!(
+    [A1](A1,),
+    [A1, A2](A1, A2),
+    [A1, A2, A3](A1, A2, A3),
+    [A1, A2, A3, A4](A1, A2, A3, A4)
+);
+represent_with_debug!(
+    [A1, A2, A3, A4, A5](A1, A2, A3, A4, A5),
+    [A1, A2, A3, A4, A5, A6](A1, A2, A3, A4, A5, A6)
+);
+represent_with_debug!([A1, A2, A3, A4, A5, A6, A7](A1, A2, A3, A4, A5, A6, A7));
+represent_with_debug!([A1, A2, A3, A4, A5, A6, A7, A8](
+    A1, A2, A3, A4, A5, A6, A7, A8
+));
+represent_with_debug!([A1, A2, A3, A4, A5, A6, A7, A8, A9](
+    A1, A2, A3, A4, A5, A6, A7, A8, A9
+));
+represent_with_debug!([A1, A2, A3, A4, A5, A6, A7, A8, A9, A10](
+    A1, A2, A3, A4, A5, A6, A7, A8, A9, A10
+));
+represent_with_debug!([A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11](
+    A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11
+));
+represent_with_debug!([A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12](
+    A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12
+));
+
+impl<T: Debug, const N: usize> Represent for [T; N] {
+    fn represent(&self) -> String {
         return format!("{:?}", self);
     }
 }
 
-impl<T> Represent for T
-where
-    T: Display + Debug,
-{
+impl<T: Represent + ?Sized> Represent for &T {
+    fn represent(&self) -> String {
+        return (**self).represent();
+    }
+}
+
+impl<T: Represent + ?Sized> Represent for &mut T {
+    fn represent(&self) -> String {
+        return (**self).represent();
+    }
+}
+
+impl<T: Represent + ?Sized> Represent for Box<T> {
+    fn represent(&self) -> String {
+        return (**self).represent();
+    }
+}
+
+impl<T: Represent + ?Sized> Represent for Rc<T> {
     fn represent(&self) -> String {
-        return format!("{}", self);
+        return (**self).represent();
+    }
+}
+
+impl<T: Represent + ?Sized> Represent for Arc<T> {
+    fn represent(&self) -> String {
+        return (**self).represent();
+    }
+}
+
+/// Renders the output of a run of a logged callable, whatever its type. Outputs
+/// of the types that [Represent] renders with [std::fmt::Display] are
+/// displayed, and any other output is rendered with [Debug]. Use
+/// [callable::LoggedCallable::represent_output_with] to render it otherwise
+/// Renders any value whose type is known where it is used, like the blanket
+/// implementation of [Represent] did before it became a closed list: with
+/// [Represent] if the type implements it, else with [std::fmt::Display], else
+/// with [Debug], and else as an empty string. The `#[task]` attribute and the
+/// `Task` derive render arguments and fields through it
+///
+/// ```
+/// struct Secret;
+///
+/// #[derive(Debug)]
+/// struct Point {
+///     x: i32,
+/// }
+///
+/// assert_eq!(running::represent!("text"), "text");
+/// assert_eq!(running::represent!(Point { x: 1 }), "Point { x: 1 }");
+/// assert_eq!(running::represent!(Secret), "");
+/// ```
+#[macro_export]
+macro_rules! represent {
+    ($value:expr) => {{
+        #[allow(unused_imports)]
+        use $crate::{
+            RepresentByDebug, RepresentByDisplay, RepresentByNothing, RepresentByRepresent,
+        };
+        (&&&&$crate::Representation(&$value)).represent_fallback()
+    }};
+}
+
+/// Wraps a value for [represent], which picks the first of the traits below
+/// that applies by autoref, from the most references down
+#[doc(hidden)]
+pub struct Representation<'a, T: ?Sized>(pub &'a T);
+
+#[doc(hidden)]
+pub trait RepresentByRepresent {
+    fn represent_fallback(&self) -> String;
+}
+
+#[doc(hidden)]
+pub trait RepresentByDisplay {
+    fn represent_fallback(&self) -> String;
+}
+
+#[doc(hidden)]
+pub trait RepresentByDebug {
+    fn represent_fallback(&self) -> String;
+}
+
+#[doc(hidden)]
+pub trait RepresentByNothing {
+    fn represent_fallback(&self) -> String;
+}
+
+impl<T: Represent + ?Sized> RepresentByRepresent for &&&Representation<'_, T> {
+    fn represent_fallback(&self) -> String {
+        return self.0.represent();
+    }
+}
+
+impl<T: std::fmt::Display + ?Sized> RepresentByDisplay for &&Representation<'_, T> {
+    fn represent_fallback(&self) -> String {
+        return self.0.to_string();
+    }
+}
+
+impl<T: Debug + ?Sized> RepresentByDebug for &Representation<'_, T> {
+    fn represent_fallback(&self) -> String {
+        return format!("{:?}", self.0);
     }
 }
 
+impl<T: ?Sized> RepresentByNothing for Representation<'_, T> {
+    fn represent_fallback(&self) -> String {
+        return String::new();
+    }
+}
+
+pub fn represent_output<T: Debug + 'static>(output: &T) -> String {
+    let output_any = output as &dyn Any;
+    macro_rules! display_if_of_type {
+        ($($displayed:ty),*) => {
+            $(
+                if let Some(displayed) = output_any.downcast_ref::<$displayed>() {
+                    return displayed.represent();
+                }
+            )*
+        };
+    }
+    display_if_of_type!(
+        bool, char, f32, f64, i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize
+    );
+    display_if_of_type!(&'static str, String, ExitStatus, RunningError);
+    return format!("{:?}", output);
+}
+
 /// A trait that represents entities that can be executed (or run). This can
 /// include functions, closures, scripts, executable binaries, operating system
 /// commands, or a set containing one or more of the above
@@ -66,8 +265,7 @@ pub trait Run {
 /// Does what the [Run] trait does, but calls the callback function with the
 /// return value when complete
 pub trait RunAndCallback: RunAndReturn {
-    fn run_and_then<C: FnOnce(Self::ReturnType) -> ()>(&mut self, callback: C)
-        -> Result<(), Error>;
+    fn run_and_then<C: FnOnce(Self::ReturnType)>(&mut self, callback: C) -> Result<(), Error>;
 }
 
 /// Does what the [Run] trait does, but returns the
@@ -78,6 +276,58 @@ pub trait RunAndReturn {
     fn run_and_return(&mut self) -> Result<Self::ReturnType, Error>;
 }
 
+/// Runs a runnable several times in a row, and returns what each run returned.
+/// Implemented for every [RunAndReturn] implementor, but only useful for
+/// runnables that can be run more than once, like callables of `Fn` and `FnMut`
+/// closures whose arguments can be cloned, and commands
+pub trait RunRepeatedly: RunAndReturn {
+    /// Runs the runnable the given number of times, stopping at the first
+    /// error
+    fn run_n(&mut self, times: usize) -> Result<Vec<Self::ReturnType>, Error> {
+        return (0..times).map(|_run| self.run_and_return()).collect();
+    }
+
+    /// Runs the runnable until what it returns does not satisfy the condition,
+    /// or until it fails. The last return value is included
+    fn repeat_while<C: FnMut(&Self::ReturnType) -> bool>(
+        &mut self,
+        mut condition: C,
+    ) -> Result<Vec<Self::ReturnType>, Error> {
+        let mut outputs = Vec::new();
+        loop {
+            let output = self.run_and_return()?;
+            let repeat = condition(&output);
+            outputs.push(output);
+            if !repeat {
+                return Ok(outputs);
+            }
+        }
+    }
+}
+
+impl<T: RunAndReturn + ?Sized> RunRepeatedly for T {}
+
+/// Describes a runnable struct for logs and jobs: its name, and its fields
+/// rendered like the arguments of a callable. Implemented by `#[derive(Task)]`
+pub trait DescribeTask {
+    /// The name of the task, which is the handle in logs and the task name in
+    /// a [runnable::Job]
+    fn task_name(&self) -> String;
+
+    /// The fields of the task, rendered like arguments
+    fn task_arguments(&self) -> String;
+}
+
+/// A trait for runnables whose inputs can be supplied just before they are
+/// run, like the arguments of a callable, or the arguments and standard input
+/// of a command. A [runnable::Job] uses it to feed the outputs of upstream
+/// tasks into downstream tasks
+pub trait AcceptInput {
+    type Input;
+
+    fn accept_input(&mut self, input: Self::Input);
+}
+
 /// Does what the [Run] trait does, but returns the
 /// debug string of the retrn value when complete
 pub trait RunAndDebug: RunAndReturn {
@@ -105,10 +355,8 @@ pub trait AsyncRun {
 pub trait AsyncRunAndCallback {
     type ReturnType;
 
-    fn async_run_and_then<C: FnOnce(Self::ReturnType) -> ()>(
-        &mut self,
-        callback: C,
-    ) -> Result<(), Error>;
+    fn async_run_and_then<C: FnOnce(Self::ReturnType)>(&mut self, callback: C)
+        -> Result<(), Error>;
 }
 
 /// Does what the [AsyncRun] trait does, but returns the
@@ -138,21 +386,69 @@ mod tests {
 
     use fern::colors::{Color, ColoredLevelConfig}; /* for setting up logging colors on the
                                                     * console */
+    use std::cell::RefCell; // for the log lines captured by each thread
     use std::sync::Once; // for calling the log initialization once
 
     // GLOBAL VARIABLES
 
+    #[allow(dead_code)]
     pub static LOGGING_INITIALIZER: Once = Once::new();
 
+    static CAPTURING_LOGGER: CapturingLogger = CapturingLogger;
+
+    thread_local! {
+        static CAPTURED_LOGS: RefCell<Option<Vec<(log::Level, String)>>> = const { RefCell::new(None) };
+    }
+
+    // STRUCT DECLARATIONS
+
+    /// Records the log lines of the threads that are capturing them
+    struct CapturingLogger;
+
+    // TRAIT IMPLEMENTATIONS
+
+    impl log::Log for CapturingLogger {
+        fn enabled(&self, _metadata: &log::Metadata) -> bool {
+            return true;
+        }
+
+        fn log(&self, record: &log::Record) {
+            CAPTURED_LOGS.with(|captured_logs| {
+                if let Some(captured_logs) = captured_logs.borrow_mut().as_mut() {
+                    captured_logs.push((record.level(), record.args().to_string()));
+                }
+            });
+        }
+
+        fn flush(&self) {}
+    }
+
     // FUNCTIONS
 
+    /// Runs a closure, and returns the level and the message of each line that
+    /// it logged on the current thread. Capturing replaces the logging of
+    /// [setup_logging] for all tests if it is set up first
+    pub fn capture_logs<F: FnOnce()>(closure: F) -> Vec<(log::Level, String)> {
+        LOGGING_INITIALIZER.call_once(|| {
+            log::set_logger(&CAPTURING_LOGGER).unwrap();
+            log::set_max_level(log::LevelFilter::Trace);
+        });
+        CAPTURED_LOGS.with(|captured_logs| *captured_logs.borrow_mut() = Some(Vec::new()));
+        closure();
+        return CAPTURED_LOGS
+            .with(|captured_logs| captured_logs.borrow_mut().take().unwrap_or_default());
+    }
+
     /// Initializes logging once, only the first time it is called
-    pub fn setup_logging(verbosity: log::LevelFilter) -> () {
+    #[allow(dead_code)]
+    pub fn setup_logging(verbosity: log::LevelFilter) {
         LOGGING_INITIALIZER.call_once(|| {
             let mut base_config = fern::Dispatch::new();
             base_config = match verbosity {
                 log::LevelFilter::Off => {
-                    base_config.level(verbosity).level_for("console-target", log::LevelFilter::Off)
+                    base_config
+                        .level(verbosity)
+                        .level_for("console-target", log::LevelFilter::Off)
                 }
                 log::LevelFilter::Trace => {
                     base_config
@@ -160,10 +456,14 @@ mod tests {
                         .level_for("console-target", log::LevelFilter::Debug)
                 }
                 log::LevelFilter::Debug => {
-                    base_config.level(verbosity).level_for("console-target", log::LevelFilter::Info)
+                    base_config
+                        .level(verbosity)
+                        .level_for("console-target", log::LevelFilter::Info)
                 }
                 log::LevelFilter::Info => {
-                    base_config.level(verbosity).level_for("console-target", log::LevelFilter::Warn)
+                    base_config
+                        .level(verbosity)
+                        .level_for("console-target", log::LevelFilter::Warn)
                 }
                 log::LevelFilter::Warn => {
                     base_config
@@ -171,7 +471,9 @@ mod tests {
                         .level_for("console-target", log::LevelFilter::Error)
                 }
                 log::LevelFilter::Error => {
-                    base_config.level(verbosity).level_for("console-target", log::LevelFilter::Off)
+                    base_config
+                        .level(verbosity)
+                        .level_for("console-target", log::LevelFilter::Off)
                 }
             };
 
@@ -211,7 +513,11 @@ mod tests {
                 })
                 .chain(std::io::stdout());
 
-            base_config.chain(file_config).chain(stdout_config).apply().unwrap();
+            base_config
+                .chain(file_config)
+                .chain(stdout_config)
+                .apply()
+                .unwrap();
         })
     }
 }
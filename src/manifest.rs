@@ -0,0 +1,578 @@
+// IMPORTS
+use crate::runnable::{Job, JobError};
+use crate::spec::JobSpec;
+use crate::{Error, RunningError};
+use serde::de::{self, DeserializeSeed, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use snafu::{Backtrace, IntoError, ResultExt, Snafu};
+use std::collections::HashSet;
+use std::fmt::{self, Display};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+
+// ERRORS
+
+#[derive(Debug, Snafu)]
+pub enum ManifestError {
+    #[snafu(display("Manifest `{}` could not be read: {}", path.display(), source))]
+    ManifestUnreadable {
+        path: PathBuf,
+        source: std::io::Error,
+        backtrace: Backtrace,
+    },
+    #[snafu(display("Manifest `{}` is not a .toml, .yaml, .yml or .json file", path.display()))]
+    ManifestFormatUnknown { path: PathBuf, backtrace: Backtrace },
+    #[snafu(display("{}: {}", location, message))]
+    ManifestInvalid {
+        location: ManifestLocation,
+        message: String,
+        backtrace: Backtrace,
+    },
+    #[snafu(display("{}: Task `{}` is defined more than once", location, task))]
+    TaskDuplicated {
+        location: ManifestLocation,
+        task: String,
+        backtrace: Backtrace,
+    },
+    #[snafu(display("{}: {}", location, source))]
+    TaskInvalid {
+        location: ManifestLocation,
+        source: Box<JobError>, // boxed, as backtraces make it large
+        backtrace: Backtrace,
+    },
+}
+
+impl ManifestError {
+    /// Where in the manifest the error is, if it is in its contents
+    pub fn location(&self) -> Option<&ManifestLocation> {
+        return match self {
+            ManifestError::ManifestInvalid { location, .. }
+            | ManifestError::TaskDuplicated { location, .. }
+            | ManifestError::TaskInvalid { location, .. } => Some(location),
+            _ => None,
+        };
+    }
+}
+
+impl From<ManifestError> for Error {
+    fn from(manifest_error: ManifestError) -> Self {
+        RunningError::Manifest(manifest_error)
+    }
+}
+
+// STRUCT DECLARATIONS
+
+/// The language of a [Manifest]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ManifestFormat {
+    Toml,
+    Yaml,
+    Json,
+}
+
+/// A file, or a string, that declares a [Job] of commands in TOML, YAML or
+/// JSON. Its contents are a [JobSpec], as in this TOML manifest:
+///
+/// ```toml
+/// error_policy = "continue_and_collect"
+///
+/// [[tasks]]
+/// name = "build"
+/// program = "cargo"
+/// args = ["build", "--release"]
+/// env = { RUSTFLAGS = "-D warnings" }
+/// timeout = 600
+/// tags = ["ci"]
+///
+/// [[tasks]]
+/// name = "test"
+/// script = "cargo test 2>&1 | tee test.log"
+/// retries = 2
+/// allowed_exit_codes = [101]
+/// depends_on = ["build"]
+/// ```
+///
+/// Errors in a manifest point at the line and column where they are found
+pub struct Manifest {
+    path: Option<PathBuf>,
+    source: String,
+    format: ManifestFormat,
+}
+
+/// Where an error is in a [Manifest]. Lines and columns start at 1, and are
+/// missing when the format does not tell where the error is
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManifestLocation {
+    pub path: Option<PathBuf>, // missing for manifests that are not files
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+}
+
+/// Goes through a manifest only to fail at the start of one of its tasks, so
+/// that the format tells where the task is
+struct TaskLocator {
+    index: usize,
+}
+
+/// Goes through the tasks of a manifest for a [TaskLocator]
+struct TasksLocator {
+    index: usize,
+}
+
+/// Fails on whatever it deserializes
+struct Failing;
+
+// STRUCT IMPLEMENTATIONS
+
+impl ManifestFormat {
+    /// The format of a manifest file, from its extension
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        let extension = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+        return match extension.as_str() {
+            "toml" => Some(ManifestFormat::Toml),
+            "yaml" | "yml" => Some(ManifestFormat::Yaml),
+            "json" => Some(ManifestFormat::Json),
+            _ => None,
+        };
+    }
+}
+
+impl Manifest {
+    /// Creates a manifest from its contents
+    pub fn new<S: Into<String>>(source: S, format: ManifestFormat) -> Self {
+        return Manifest {
+            path: None,
+            source: source.into(),
+            format,
+        };
+    }
+
+    /// Reads a manifest file, in the format given by its extension
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let format = match ManifestFormat::from_path(path) {
+            Some(format) => format,
+            None => return ManifestFormatUnknown { path }.fail().map_err(Error::from),
+        };
+        let source = std::fs::read_to_string(path)
+            .context(ManifestUnreadable { path })
+            .map_err(Error::from)?;
+        return Ok(Manifest {
+            path: Some(path.to_path_buf()),
+            source,
+            format,
+        });
+    }
+
+    /// Parses the manifest, and validates its tasks: their names must be
+    /// unique, and their dependencies must exist and not form a cycle
+    pub fn spec(&self) -> Result<JobSpec, Error> {
+        return self.spec_and_job().map(|(spec, _job)| spec);
+    }
+
+    /// Parses and validates the manifest, and builds the job that it declares
+    pub fn job(&self) -> Result<Job, Error> {
+        return self.spec_and_job().map(|(_spec, job)| job);
+    }
+
+    /// Parses the manifest, and validates its tasks by building the job that
+    /// it declares, which is returned along with the spec
+    fn spec_and_job(&self) -> Result<(JobSpec, Job), Error> {
+        let spec = self
+            .deserialize(PhantomData::<JobSpec>)
+            .map_err(|(location, message)| {
+                Error::from(ManifestInvalid { location, message }.build())
+            })?;
+
+        let mut names = HashSet::new();
+        if let Some(index) = spec
+            .tasks
+            .iter()
+            .position(|task| !names.insert(task.name.as_str()))
+        {
+            return TaskDuplicated {
+                location: self.locate_task(index),
+                task: spec.tasks[index].name.clone(),
+            }
+            .fail()
+            .map_err(Error::from);
+        }
+        return match spec.clone().into_job() {
+            Ok(job) => Ok((spec, job)),
+            Err(RunningError::Job(job_error)) => {
+                let task = match &job_error {
+                    JobError::DependencyMissing { task, .. }
+                    | JobError::DependencyAmbiguous { task, .. } => Some(task),
+                    JobError::DependencyCycle { cycle, .. } => cycle.first(),
+                    _ => None,
+                };
+                let location = match task
+                    .and_then(|task| spec.tasks.iter().position(|spec| &spec.name == task))
+                {
+                    Some(index) => self.locate_task(index),
+                    None => self.location(None),
+                };
+                Err(TaskInvalid { location }
+                    .into_error(Box::new(job_error))
+                    .into())
+            }
+            Err(error) => Err(error),
+        };
+    }
+
+    /// Deserializes the manifest with a seed, in its format. Errors are given
+    /// with their location, and their message without it
+    fn deserialize<T, S>(&self, seed: S) -> Result<T, (ManifestLocation, String)>
+    where
+        S: for<'de> DeserializeSeed<'de, Value = T>,
+    {
+        return match self.format {
+            ManifestFormat::Toml => {
+                seed.deserialize(toml::Deserializer::new(&self.source))
+                    .map_err(|error| {
+                        let position = error.span().map(|span| self.position(span.start));
+                        return (self.location(position), error.message().to_string());
+                    })
+            }
+            ManifestFormat::Yaml => {
+                seed.deserialize(serde_yaml::Deserializer::from_str(&self.source))
+                    .map_err(|error| {
+                        let position = error
+                            .location()
+                            .map(|location| (location.line(), location.column()));
+                        return (
+                            self.location(position),
+                            without_position(error.to_string(), position),
+                        );
+                    })
+            }
+            ManifestFormat::Json => {
+                let mut deserializer = serde_json::Deserializer::from_str(&self.source);
+                seed.deserialize(&mut deserializer)
+                    .and_then(|value| deserializer.end().map(|()| value))
+                    .map_err(|error| {
+                        let position = Some((error.line(), error.column()))
+                            .filter(|(line, _column)| *line > 0);
+                        return (
+                            self.location(position),
+                            without_position(error.to_string(), position),
+                        );
+                    })
+            }
+        };
+    }
+
+    /// Where the task at the given index starts
+    fn locate_task(&self, index: usize) -> ManifestLocation {
+        return match self.deserialize(TaskLocator { index }) {
+            Ok(()) => self.location(None),
+            Err((location, _message)) => location,
+        };
+    }
+
+    /// The line and column of a byte offset into the manifest
+    fn position(&self, offset: usize) -> (usize, usize) {
+        let before = &self.source[..offset.min(self.source.len())];
+        let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+        return (
+            before.matches('\n').count() + 1,
+            before[line_start..].chars().count() + 1,
+        );
+    }
+
+    /// A location in the manifest, at the given line and column
+    fn location(&self, position: Option<(usize, usize)>) -> ManifestLocation {
+        return ManifestLocation {
+            path: self.path.clone(),
+            line: position.map(|(line, _column)| line),
+            column: position.map(|(_line, column)| column),
+        };
+    }
+}
+
+// FUNCTIONS
+
+/// Removes the position that YAML and JSON errors end their messages with
+fn without_position(message: String, position: Option<(usize, usize)>) -> String {
+    return match position {
+        Some((line, column)) => {
+            let suffix = format!(" at line {} column {}", line, column);
+            message
+                .strip_suffix(&suffix)
+                .map(str::to_string)
+                .unwrap_or(message)
+        }
+        None => message,
+    };
+}
+
+// TRAIT IMPLEMENTATIONS
+
+impl Display for ManifestLocation {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.path.as_ref() {
+            Some(path) => write!(formatter, "{}", path.display())?,
+            None => write!(formatter, "<manifest>")?,
+        }
+        if let Some(line) = self.line {
+            write!(formatter, ":{}", line)?;
+        }
+        if let Some(column) = self.column {
+            write!(formatter, ":{}", column)?;
+        }
+        return Ok(());
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for TaskLocator {
+    type Value = ();
+
+    fn deserialize<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Self::Value, D::Error> {
+        return deserializer.deserialize_map(self);
+    }
+}
+
+impl<'de> Visitor<'de> for TaskLocator {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return formatter.write_str("a job manifest");
+    }
+
+    fn visit_map<M: MapAccess<'de>>(self, mut map: M) -> Result<Self::Value, M::Error> {
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "tasks" => map.next_value_seed(TasksLocator { index: self.index })?,
+                _ => map.next_value::<IgnoredAny>().map(|_value| ())?,
+            }
+        }
+        return Ok(());
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for TasksLocator {
+    type Value = ();
+
+    fn deserialize<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Self::Value, D::Error> {
+        return deserializer.deserialize_seq(self);
+    }
+}
+
+impl<'de> Visitor<'de> for TasksLocator {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return formatter.write_str("a list of tasks");
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut tasks: A) -> Result<Self::Value, A::Error> {
+        for _index in 0..self.index {
+            tasks.next_element::<IgnoredAny>()?;
+        }
+        tasks.next_element_seed(Failing)?;
+        while tasks.next_element::<IgnoredAny>()?.is_some() {}
+        return Ok(());
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for Failing {
+    type Value = ();
+
+    fn deserialize<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Self::Value, D::Error> {
+        return deserializer.deserialize_any(self);
+    }
+}
+
+impl<'de> Visitor<'de> for Failing {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return formatter.write_str("nothing");
+    }
+
+    fn visit_map<M: MapAccess<'de>>(self, _map: M) -> Result<Self::Value, M::Error> {
+        return Err(de::Error::custom("located"));
+    }
+}
+
+// TESTS
+
+#[cfg(test)]
+mod tests {
+
+    // IMPORTS
+
+    use super::{Manifest, ManifestError, ManifestFormat, ManifestLocation};
+    use crate::runnable::{JobError, TaskOutcome};
+    use crate::{ErrorCode, RunAndReturn, RunningError};
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    // FUNCTIONS
+
+    /// The location of a manifest error, as `line:column`
+    fn position(manifest: &Manifest) -> String {
+        return match manifest.spec().unwrap_err() {
+            RunningError::Manifest(error) => {
+                let location = error.location().unwrap();
+                format!("{}:{}", location.line.unwrap(), location.column.unwrap())
+            }
+            error => panic!("Expected a manifest error, but got: {}", error),
+        };
+    }
+
+    // TESTS
+
+    #[test]
+    fn formats() {
+        let toml = r#"
+            error_policy = "continue_and_collect"
+
+            [[tasks]]
+            name = "greet"
+            program = "sh"
+            args = ["-c", "echo $GREETING"]
+            env = { GREETING = "Hello" }
+            timeout = 5
+            tags = ["greeting"]
+
+            [[tasks]]
+            name = "grep"
+            script = "echo World | grep -q Mars"
+            allowed_exit_codes = [1]
+            retries = 1
+            depends_on = ["greet"]
+        "#;
+        let yaml = r#"
+            error_policy: continue_and_collect
+            tasks:
+              - name: greet
+                program: sh
+                args: ["-c", "echo $GREETING"]
+                env: {GREETING: Hello}
+                timeout: 5
+                tags: [greeting]
+              - name: grep
+                script: echo World | grep -q Mars
+                allowed_exit_codes: [1]
+                retries: 1
+                depends_on: [greet]
+        "#;
+        let json = r#"{
+            "error_policy": "continue_and_collect",
+            "tasks": [
+                {"name": "greet", "program": "sh", "args": ["-c", "echo $GREETING"], "env": {"GREETING": "Hello"}, "timeout": 5, "tags": ["greeting"]},
+                {"name": "grep", "script": "echo World | grep -q Mars", "allowed_exit_codes": [1], "retries": 1, "depends_on": ["greet"]}
+            ]
+        }"#;
+        for (source, format) in [
+            (toml, ManifestFormat::Toml),
+            (yaml, ManifestFormat::Yaml),
+            (json, ManifestFormat::Json),
+        ] {
+            let manifest = Manifest::new(source, format);
+            let spec = manifest.spec().unwrap();
+            assert_eq!(spec.tasks[0].timeout, Some(Duration::from_secs(5)));
+            assert_eq!(spec.tasks[1].retries, 1);
+
+            let report = manifest.job().unwrap().run_and_return().unwrap();
+            assert_eq!(report.tasks.len(), 2);
+            assert!(report
+                .tasks
+                .iter()
+                .all(|task| task.outcome == TaskOutcome::Succeeded));
+            assert_eq!(report.tagged("greeting").count(), 1);
+        }
+    }
+
+    #[test]
+    fn locations() {
+        let manifest = Manifest::new(
+            "[[tasks]]\nname = \"a\"\nprogram = \"true\"\nretry = 1\n",
+            ManifestFormat::Toml,
+        );
+        assert_eq!(position(&manifest), "4:1");
+        let manifest = Manifest::new(
+            "tasks:\n  - name: a\n    program: true\n    retry: 1\n",
+            ManifestFormat::Yaml,
+        );
+        assert_eq!(position(&manifest), "4:5");
+        let manifest = Manifest::new(
+            "{\"tasks\": [\n  {\"name\": \"a\", \"program\": \"true\", \"retry\": 1}\n]}",
+            ManifestFormat::Json,
+        );
+        assert_eq!(position(&manifest), "2:42");
+
+        let toml = "[[tasks]]\nname = \"a\"\nprogram = \"true\"\n\n[[tasks]]\nname = \"b\"\nprogram = \"true\"\ndepends_on = [\"c\"]\n";
+        let error = Manifest::new(toml, ManifestFormat::Toml)
+            .spec()
+            .unwrap_err();
+        assert_eq!(error.code(), ErrorCode::ManifestTaskInvalid);
+        assert!(matches!(
+            &error,
+            RunningError::Manifest(ManifestError::TaskInvalid { source, .. })
+                if matches!(**source, JobError::DependencyMissing { .. })
+        ));
+        assert!(error
+            .to_string()
+            .starts_with("<manifest>:5:1: Task `b` depends on `c`"));
+
+        let yaml = "tasks:\n  - name: a\n    script: 'true'\n  - name: a\n    script: 'true'\n";
+        let error = Manifest::new(yaml, ManifestFormat::Yaml)
+            .spec()
+            .unwrap_err();
+        assert_eq!(error.code(), ErrorCode::ManifestTaskDuplicated);
+        assert!(error.to_string().starts_with("<manifest>:4:"));
+
+        let json = "{\"tasks\": [\n{\"name\": \"a\", \"script\": \"true\", \"depends_on\": [\"b\"]},\n{\"name\": \"b\", \"script\": \"true\", \"depends_on\": [\"a\"]}\n]}";
+        let error = Manifest::new(json, ManifestFormat::Json)
+            .spec()
+            .unwrap_err();
+        assert_eq!(error.code(), ErrorCode::ManifestTaskInvalid);
+        assert!(error.to_string().starts_with("<manifest>:2:"));
+
+        let yaml = "tasks:\n  - name: a\n    program: ls\n    script: ls\n";
+        let error = Manifest::new(yaml, ManifestFormat::Yaml)
+            .spec()
+            .unwrap_err();
+        assert_eq!(error.code(), ErrorCode::ManifestInvalid);
+        assert!(error
+            .to_string()
+            .contains("task `a` has both a program and a script"));
+    }
+
+    #[test]
+    fn files() {
+        let path =
+            std::env::temp_dir().join(format!("running-manifest-{}.yml", std::process::id()));
+        std::fs::write(&path, "tasks:\n  - name: a\n    program: \"\"\n").unwrap();
+        let error = Manifest::load(&path).unwrap().spec().unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+        match error {
+            RunningError::Manifest(error) => {
+                assert_eq!(
+                    error.location(),
+                    Some(&ManifestLocation {
+                        path: Some(path.clone()),
+                        line: Some(2),
+                        column: Some(3),
+                    })
+                )
+            }
+            error => panic!("Expected a manifest error, but got: {}", error),
+        }
+
+        let error = Manifest::load("jobs.ini").err().unwrap();
+        assert_eq!(error.code(), ErrorCode::ManifestFormatUnknown);
+        let error = Manifest::load(PathBuf::from("missing.toml")).err().unwrap();
+        assert_eq!(error.code(), ErrorCode::ManifestUnreadable);
+    }
+}
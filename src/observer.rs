@@ -0,0 +1,162 @@
+// IMPORTS
+use crate::runnable::JobReport;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+// STRUCT DECLARATIONS
+
+/// When and for which job an event of a [JobObserver] happened
+#[derive(Debug, Clone)]
+pub struct JobEvent {
+    pub job_id: usize,
+    pub timestamp: SystemTime,
+}
+
+/// When and for which task an event of a [JobObserver] happened. The task ID
+/// is the one generated by [crate::generate_task_id] when the task was pushed
+#[derive(Debug, Clone)]
+pub struct TaskEvent {
+    pub job_id: usize,
+    pub task_id: usize,
+    pub name: String,
+    pub timestamp: SystemTime,
+}
+
+/// The stream of a command that an output line was read from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// Logs the lifecycle events of a job through the `log` crate, with task
+/// events at the debug level and failures at the warn level
+#[derive(Debug, Clone, Default)]
+pub struct LogObserver;
+
+// TRAIT DECLARATIONS
+
+/// Receives the lifecycle events of a [crate::runnable::Job]. Every method
+/// does nothing by default, so that implementors only need to override the
+/// events they are interested in. Events of tasks that run in parallel arrive
+/// from several threads at once
+#[allow(unused_variables)]
+pub trait JobObserver: Send + Sync {
+    fn job_started(&self, event: &JobEvent) {}
+
+    fn job_finished(&self, event: &JobEvent, report: &JobReport) {}
+
+    /// The task is ready to run, because all of its dependencies succeeded,
+    /// and is waiting for a free worker
+    fn task_queued(&self, event: &TaskEvent) {}
+
+    fn task_started(&self, event: &TaskEvent) {}
+
+    fn task_succeeded(&self, event: &TaskEvent) {}
+
+    fn task_failed(&self, event: &TaskEvent, message: &str) {}
+
+    fn task_skipped(&self, event: &TaskEvent) {}
+
+    fn task_cancelled(&self, event: &TaskEvent) {}
+
+    /// The task failed, and is being run again
+    fn task_retried(&self, event: &TaskEvent, attempt: usize, message: &str) {}
+
+    /// A command of the task wrote a line, given without its line ending
+    fn output_line(&self, event: &TaskEvent, stream: OutputStream, line: &str) {}
+}
+
+// TRAIT IMPLEMENTATIONS
+
+impl<O: JobObserver + ?Sized> JobObserver for Arc<O> {
+    fn job_started(&self, event: &JobEvent) {
+        (**self).job_started(event);
+    }
+
+    fn job_finished(&self, event: &JobEvent, report: &JobReport) {
+        (**self).job_finished(event, report);
+    }
+
+    fn task_queued(&self, event: &TaskEvent) {
+        (**self).task_queued(event);
+    }
+
+    fn task_started(&self, event: &TaskEvent) {
+        (**self).task_started(event);
+    }
+
+    fn task_succeeded(&self, event: &TaskEvent) {
+        (**self).task_succeeded(event);
+    }
+
+    fn task_failed(&self, event: &TaskEvent, message: &str) {
+        (**self).task_failed(event, message);
+    }
+
+    fn task_skipped(&self, event: &TaskEvent) {
+        (**self).task_skipped(event);
+    }
+
+    fn task_cancelled(&self, event: &TaskEvent) {
+        (**self).task_cancelled(event);
+    }
+
+    fn task_retried(&self, event: &TaskEvent, attempt: usize, message: &str) {
+        (**self).task_retried(event, attempt, message);
+    }
+
+    fn output_line(&self, event: &TaskEvent, stream: OutputStream, line: &str) {
+        (**self).output_line(event, stream, line);
+    }
+}
+
+impl JobObserver for LogObserver {
+    fn job_started(&self, event: &JobEvent) {
+        log::info!("Job {} started", event.job_id);
+    }
+
+    fn job_finished(&self, event: &JobEvent, report: &JobReport) {
+        log::info!(
+            "Job {} finished in {:?}. {} of {} tasks did not succeed",
+            event.job_id,
+            report.duration,
+            report.tasks.len() - report.succeeded_count(),
+            report.tasks.len()
+        );
+    }
+
+    fn task_started(&self, event: &TaskEvent) {
+        log::debug!("[{}] {} started", event.task_id, event.name);
+    }
+
+    fn task_succeeded(&self, event: &TaskEvent) {
+        log::debug!("[{}] {} succeeded", event.task_id, event.name);
+    }
+
+    fn task_failed(&self, event: &TaskEvent, message: &str) {
+        log::warn!("[{}] {} failed: {}", event.task_id, event.name, message);
+    }
+
+    fn task_skipped(&self, event: &TaskEvent) {
+        log::debug!("[{}] {} skipped", event.task_id, event.name);
+    }
+
+    fn task_cancelled(&self, event: &TaskEvent) {
+        log::debug!("[{}] {} cancelled", event.task_id, event.name);
+    }
+
+    fn task_retried(&self, event: &TaskEvent, attempt: usize, message: &str) {
+        log::warn!(
+            "[{}] {} failed, starting attempt {}: {}",
+            event.task_id,
+            event.name,
+            attempt,
+            message
+        );
+    }
+
+    fn output_line(&self, event: &TaskEvent, _stream: OutputStream, line: &str) {
+        log::trace!("[{}] {}: {}", event.task_id, event.name, line);
+    }
+}
@@ -0,0 +1,419 @@
+// IMPORTS
+use crate::callable::CallableError;
+use crate::cancellation::CancellationToken;
+use crate::runnable::TaskContext;
+use crate::{AcceptInput, DescribeTask, Run, RunAndReturn};
+use crate::{Error, RunningError};
+use snafu::{Backtrace, IntoError, Snafu};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// CONSTANTS
+
+/// How often a retry that is waiting out its backoff checks whether it was
+/// cancelled
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+// ERRORS
+
+#[derive(Debug, Snafu)]
+pub enum RetryError {
+    #[snafu(display(
+        "Runnable cannot be run again for attempt {}: {}. Callables of `FnOnce` closures cannot be retried. Attempt {} failed: {}",
+        attempt,
+        message,
+        attempt - 1,
+        source
+    ))]
+    NotRerunnable {
+        attempt: usize,
+        message: String,
+        source: Box<dyn std::error::Error + Send + Sync>, // the error of the previous attempt
+        backtrace: Backtrace,
+    },
+}
+
+impl From<RetryError> for Error {
+    fn from(retry_error: RetryError) -> Self {
+        RunningError::Retry(retry_error)
+    }
+}
+
+// STRUCT DECLARATIONS
+
+/// How long a [Retry] waits before each new attempt
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Backoff {
+    /// Retry right away
+    Immediate,
+    /// Wait the same duration before every attempt
+    Fixed(Duration),
+    /// Wait `initial` before the second attempt, and `factor` times longer
+    /// before each attempt after that, but never longer than `max`
+    Exponential {
+        initial: Duration,
+        factor: f64,
+        max: Duration,
+    },
+}
+
+/// Decides whether a failed attempt of a [Retry] should be retried
+type RetryPredicate = Box<dyn Fn(&Error) -> bool + Send + Sync>;
+
+/// Runs a runnable again when it fails, up to a maximum number of attempts,
+/// waiting between attempts according to a [Backoff]. Only the runnables that
+/// can be run more than once can be retried, so a callable of an `FnOnce`
+/// closure fails with [RetryError::NotRerunnable] instead of being retried
+pub struct Retry<T> {
+    runnable: T,
+    max_attempts: usize,
+    backoff: Backoff,
+    jitter: bool,
+    predicate: Option<RetryPredicate>,
+}
+
+// STRUCT IMPLEMENTATIONS
+
+impl Backoff {
+    /// Doubles the wait before each attempt, starting from the given duration,
+    /// up to a minute
+    pub fn exponential(initial: Duration) -> Self {
+        return Backoff::Exponential {
+            initial,
+            factor: 2.0,
+            max: Duration::from_secs(60),
+        };
+    }
+
+    /// How long to wait after the given failed attempt, counting from 1
+    fn delay(&self, attempt: usize) -> Duration {
+        return match *self {
+            Backoff::Immediate => Duration::default(),
+            Backoff::Fixed(delay) => delay,
+            Backoff::Exponential {
+                initial,
+                factor,
+                max,
+            } => {
+                let exponent = (attempt - 1).min(i32::MAX as usize) as i32;
+                let seconds = initial.as_secs_f64() * factor.powi(exponent);
+                Duration::try_from_secs_f64(seconds).map_or(max, |delay| delay.min(max))
+            }
+        };
+    }
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        return Backoff::Immediate;
+    }
+}
+
+impl<T> Retry<T> {
+    /// Wraps a runnable so that it is attempted up to 3 times, retrying right
+    /// away on any error
+    pub fn new(runnable: T) -> Self {
+        return Retry {
+            runnable,
+            max_attempts: 3,
+            backoff: Backoff::default(),
+            jitter: false,
+            predicate: None,
+        };
+    }
+
+    /// Sets how many times the runnable is attempted in total, including the
+    /// first attempt
+    pub fn max_attempts(mut self, max_attempts: usize) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        return self;
+    }
+
+    /// Sets how long to wait before each new attempt
+    pub fn backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        return self;
+    }
+
+    /// Randomizes each wait to between half of and the full backoff, so that
+    /// many runnables that failed together do not all retry at once
+    pub fn jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        return self;
+    }
+
+    /// Only retries the errors for which the predicate returns true. Other
+    /// errors are returned right away
+    pub fn retry_if<P>(mut self, predicate: P) -> Self
+    where
+        P: Fn(&Error) -> bool + Send + Sync + 'static,
+    {
+        self.predicate = Some(Box::new(predicate));
+        return self;
+    }
+
+    /// Only retries commands that exit with one of the given exit codes
+    pub fn retry_on_exit_codes(self, exit_codes: &[i32]) -> Self {
+        let exit_codes = exit_codes.to_vec();
+        return self.retry_if(move |error| {
+            return match error {
+                RunningError::Command(command_error) => {
+                    command_error
+                        .exit_code()
+                        .is_some_and(|exit_code| exit_codes.contains(&exit_code))
+                }
+                _ => false,
+            };
+        });
+    }
+
+    /// Returns the wrapped runnable
+    pub fn into_inner(self) -> T {
+        return self.runnable;
+    }
+
+    /// Makes attempts until one succeeds, the error is not to be retried, or
+    /// the attempts run out. Every failed attempt is logged, and reported to
+    /// the observers of the job that the retry runs in, if any
+    fn attempt<O, C>(&mut self, mut run_once: C) -> Result<O, Error>
+    where
+        C: FnMut(&mut T) -> Result<O, Error>,
+    {
+        let mut attempt = 1;
+        let mut previous_error = None;
+        loop {
+            let error = match run_once(&mut self.runnable) {
+                Ok(output) => return Ok(output),
+                Err(error) => error,
+            };
+            if let (true, Some(previous_error)) = (is_spent_callable(&error), previous_error.take())
+            {
+                let not_rerunnable = NotRerunnable {
+                    attempt,
+                    message: error.to_string(),
+                };
+                return Err(not_rerunnable.into_error(Box::new(previous_error)).into());
+            }
+            let retriable = self
+                .predicate
+                .as_ref()
+                .is_none_or(|predicate| predicate(&error));
+            if !retriable
+                || attempt >= self.max_attempts
+                || CancellationToken::current().is_cancelled()
+            {
+                if attempt > 1 {
+                    log::warn!(
+                        "Attempt {} of {} failed, giving up: {}",
+                        attempt,
+                        self.max_attempts,
+                        error
+                    );
+                }
+                return Err(error);
+            }
+
+            let delay = self.delay(attempt);
+            log::warn!(
+                "Attempt {} of {} failed, retrying in {:?}: {}",
+                attempt,
+                self.max_attempts,
+                delay,
+                error
+            );
+            attempt += 1;
+            if let Some(context) = TaskContext::current() {
+                let message = error.to_string();
+                context
+                    .notify_task(|observer, event| observer.task_retried(event, attempt, &message));
+            }
+            if !sleep_unless_cancelled(delay) {
+                return Err(error);
+            }
+            previous_error = Some(error);
+        }
+    }
+
+    /// How long to wait after the given failed attempt, with jitter if enabled
+    fn delay(&self, attempt: usize) -> Duration {
+        let delay = self.backoff.delay(attempt);
+        if !self.jitter {
+            return delay;
+        }
+        let random = RandomState::new().build_hasher().finish() as f64 / u64::MAX as f64;
+        return delay.mul_f64(0.5 + random / 2.0);
+    }
+}
+
+// FUNCTIONS
+
+/// Whether the error says that a callable lost its handle or arguments in a
+/// previous run, and cannot be run again
+fn is_spent_callable(error: &Error) -> bool {
+    return matches!(
+        error,
+        RunningError::Callable(CallableError::CallableHandleMissing { .. })
+            | RunningError::Callable(CallableError::CallableArgumentsMissing { .. })
+    );
+}
+
+/// Sleeps for the given duration, but wakes up early if the task running on
+/// the current thread is cancelled. Returns whether the full duration passed
+fn sleep_unless_cancelled(duration: Duration) -> bool {
+    let token = CancellationToken::current();
+    let deadline = Instant::now() + duration;
+    loop {
+        if token.is_cancelled() {
+            return false;
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            return true;
+        }
+        thread::sleep(CANCEL_POLL_INTERVAL.min(deadline - now));
+    }
+}
+
+// TRAIT IMPLEMENTATIONS
+
+impl<T: RunAndReturn> RunAndReturn for Retry<T> {
+    type ReturnType = T::ReturnType;
+
+    fn run_and_return(&mut self) -> Result<Self::ReturnType, Error> {
+        return self.attempt(|runnable| runnable.run_and_return());
+    }
+}
+
+impl<T: Run> Run for Retry<T> {
+    fn run(&mut self) -> Result<(), Error> {
+        return self.attempt(|runnable| runnable.run());
+    }
+}
+
+impl<T: AcceptInput> AcceptInput for Retry<T> {
+    type Input = T::Input;
+
+    fn accept_input(&mut self, input: Self::Input) {
+        self.runnable.accept_input(input);
+    }
+}
+
+impl<T: DescribeTask> DescribeTask for Retry<T> {
+    fn task_name(&self) -> String {
+        return self.runnable.task_name();
+    }
+
+    fn task_arguments(&self) -> String {
+        return self.runnable.task_arguments();
+    }
+}
+
+// TESTS
+
+#[cfg(test)]
+mod tests {
+
+    // IMPORTS
+
+    use super::{Backoff, Retry, RetryError};
+    use crate::callable::{Callable, CallableError};
+    use crate::instruction::Command;
+    use crate::{RunAndReturn, RunningError};
+    use std::error::Error;
+    use std::time::Duration;
+
+    // FUNCTIONS
+
+    /// A command that fails with exit code 3 until it has run the given number
+    /// of times, counting its runs in the given file
+    fn flaky_command(counter: &std::path::Path, failures: usize) -> Command {
+        let script = format!(
+            "echo >> '{0}'; [ $(wc -l < '{0}') -gt {1} ] || exit 3",
+            counter.display(),
+            failures
+        );
+        return Command::new("sh").args(["-c", &script]);
+    }
+
+    // TESTS
+
+    #[test]
+    fn backoff() {
+        let backoff = Backoff::Exponential {
+            initial: Duration::from_millis(100),
+            factor: 2.0,
+            max: Duration::from_millis(300),
+        };
+        assert_eq!(backoff.delay(1), Duration::from_millis(100));
+        assert_eq!(backoff.delay(2), Duration::from_millis(200));
+        assert_eq!(backoff.delay(3), Duration::from_millis(300));
+        assert_eq!(backoff.delay(100), Duration::from_millis(300));
+    }
+
+    #[test]
+    fn retry_until_success() {
+        let counter =
+            std::env::temp_dir().join(format!("running-retry-{}", crate::generate_task_id()));
+        let mut retry = Retry::new(flaky_command(&counter, 2))
+            .max_attempts(5)
+            .backoff(Backoff::Fixed(Duration::from_millis(1)))
+            .jitter(true)
+            .retry_on_exit_codes(&[3]);
+        assert!(retry.run_and_return().is_ok());
+        let runs = std::fs::read_to_string(&counter).unwrap().lines().count();
+        std::fs::remove_file(&counter).unwrap();
+        assert_eq!(runs, 3);
+    }
+
+    #[test]
+    fn retry_predicate() {
+        let counter =
+            std::env::temp_dir().join(format!("running-retry-{}", crate::generate_task_id()));
+        let mut retry = Retry::new(flaky_command(&counter, 2)).retry_on_exit_codes(&[4]);
+        assert!(retry.run_and_return().is_err());
+        let runs = std::fs::read_to_string(&counter).unwrap().lines().count();
+        std::fs::remove_file(&counter).unwrap();
+        assert_eq!(runs, 1);
+    }
+
+    #[test]
+    fn rerun_fn_callable() {
+        let mut attempts = 0;
+        let mut retry = Retry::new(Callable::new(|| {
+            attempts += 1;
+            if attempts < 3 {
+                panic!("Panicking test...");
+            }
+            attempts
+        }));
+        assert_eq!(retry.run_and_return().unwrap(), 3);
+    }
+
+    #[test]
+    fn fn_once_not_rerunnable() {
+        let message = String::from("Panicking test...");
+        let mut retry = Retry::new(Callable::once(move || -> () {
+            drop(message);
+            panic!("Panicking test...")
+        }));
+        let error = retry.run_and_return().unwrap_err();
+        assert!(matches!(
+            error,
+            RunningError::Retry(RetryError::NotRerunnable { attempt: 2, .. })
+        ));
+        let previous_error = error
+            .source()
+            .unwrap()
+            .downcast_ref::<RunningError>()
+            .unwrap();
+        assert!(matches!(
+            previous_error,
+            RunningError::Callable(CallableError::CallablePanicked { message, .. }) if message == "Panicking test..."
+        ));
+        assert!(error
+            .to_string()
+            .contains(". Attempt 1 failed: Callable panicked in thread"));
+    }
+}
@@ -1,7 +1,2058 @@
 // IMPORTS
-use crate::Run;
-use std::collections::VecDeque;
+use crate::callable::{panic_message, LoggingFormat};
+use crate::cancellation::CancellationToken;
+use crate::observer::{JobEvent, JobObserver, OutputStream, TaskEvent};
+#[cfg(feature = "signal_support")]
+use crate::signal::{Interruption, TrackedProcessGroup};
+#[cfg(feature = "tracing_support")]
+use crate::trace::{self, TracedRun};
+use crate::{generate_task_id, AcceptInput, DescribeTask, Error, Run, RunAndReturn, RunningError};
+#[cfg(feature = "serde_support")]
+use serde::{Deserialize, Serialize};
+use snafu::{Backtrace, OptionExt, Snafu};
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::fmt::{self, Display};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
 
+// ERRORS
+
+#[derive(Debug, Snafu)]
+pub enum JobError {
+    #[snafu(display(
+        "Job {} failed. {} of {} tasks did not succeed",
+        report.id,
+        report.failures().count(),
+        report.tasks.len()
+    ))]
+    JobFailed {
+        report: JobReport,
+        backtrace: Backtrace,
+    },
+    #[snafu(display(
+        "Job {} was cancelled. {} of {} tasks succeeded",
+        report.id,
+        report.succeeded_count(),
+        report.tasks.len()
+    ))]
+    JobCancelled {
+        report: JobReport,
+        backtrace: Backtrace,
+    },
+    #[snafu(display(
+        "Job {} was interrupted by a signal. {} of {} tasks succeeded",
+        report.id,
+        report.succeeded_count(),
+        report.tasks.len()
+    ))]
+    JobInterrupted {
+        report: JobReport,
+        backtrace: Backtrace,
+    },
+    #[snafu(display("Task `{}` does not exist in job {}", task, job_id))]
+    TaskMissing {
+        task: Dependency,
+        job_id: usize,
+        backtrace: Backtrace,
+    },
+    #[snafu(display("Task `{}` names more than one task in job {}", task, job_id))]
+    TaskAmbiguous {
+        task: Dependency,
+        job_id: usize,
+        backtrace: Backtrace,
+    },
+    #[snafu(display("Task `{}` depends on `{}`, which does not exist", task, dependency))]
+    DependencyMissing {
+        task: String,
+        dependency: Dependency,
+        backtrace: Backtrace,
+    },
+    #[snafu(display(
+        "Task `{}` depends on `{}`, which names more than one task",
+        task,
+        dependency
+    ))]
+    DependencyAmbiguous {
+        task: String,
+        dependency: Dependency,
+        backtrace: Backtrace,
+    },
+    #[snafu(display("Upstream outputs missing. They were taken from their task handles before the downstream task ran"))]
+    UpstreamOutputMissing { backtrace: Backtrace },
+    #[snafu(display("Tasks depend on each other in a cycle: {}", cycle.join(" -> ")))]
+    DependencyCycle {
+        cycle: Vec<String>,
+        backtrace: Backtrace,
+    },
+    #[snafu(display("Task timed out after {:?}", timeout))]
+    TaskTimedOut {
+        task: String,
+        timeout: Duration,
+        backtrace: Backtrace,
+    },
+}
+
+impl From<JobError> for Error {
+    fn from(job_error: JobError) -> Self {
+        RunningError::Job(job_error)
+    }
+}
+
+// STRUCT DECLARATIONS
+
+/// Decides what a [Job] does when one of its tasks fails. Serialized in snake
+/// case, as `"stop_at_first_failure"`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde_support", serde(rename_all = "snake_case"))]
+pub enum ErrorPolicy {
+    /// Skip the remaining tasks after the first failure, and fail the job
+    StopAtFirstFailure,
+    /// Run all tasks, and succeed with a report that lists every failure
+    ContinueAndCollect,
+    /// Run all tasks, and fail the job at the end if any of them failed
+    FailAtEnd,
+}
+
+impl Default for ErrorPolicy {
+    fn default() -> Self {
+        return ErrorPolicy::StopAtFirstFailure;
+    }
+}
+
+/// The outcome of a single task within a [Job]. Serialized in snake case, as
+/// `"succeeded"`, or as `{"failed": "message"}` for a failure
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde_support", serde(rename_all = "snake_case"))]
+pub enum TaskOutcome {
+    Succeeded,
+    /// The task returned an error, whose message is stored
+    Failed(String),
+    /// The task was never run, because an earlier failure stopped the job, or
+    /// because one of the tasks it depends on did not succeed
+    Skipped,
+    /// The task was stopped while running, because the job was cancelled or
+    /// interrupted, or because a sibling task failed. Also the outcome of the
+    /// tasks that never ran because the job was cancelled or interrupted
+    Cancelled,
+}
+
+/// What happened to a single task within a [Job]. Durations are serialized as
+/// seconds
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct TaskReport {
+    pub id: usize,
+    pub name: String,
+    pub outcome: TaskOutcome,
+    #[cfg_attr(feature = "serde_support", serde(with = "crate::spec::seconds"))]
+    pub duration: Duration,
+    pub tags: Vec<String>,
+}
+
+/// What happened to each task of a [Job], in the order the tasks were pushed.
+/// Durations are serialized as seconds
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct JobReport {
+    pub id: usize,
+    pub tasks: Vec<TaskReport>,
+    #[cfg_attr(feature = "serde_support", serde(with = "crate::spec::seconds"))]
+    pub duration: Duration,
+    pub cancelled: bool,   // whether the cancellation token of the job fired
+    pub interrupted: bool, // whether the job was interrupted by a signal
+}
+
+/// Refers to a task of a [Job] that another task depends on, either by its
+/// unique ID or by its name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Dependency {
+    Id(usize),
+    Name(String),
+}
+
+/// What a task returned, shared between the task and its handles, and empty
+/// until the task has run
+type SharedResult<R> = Arc<Mutex<Option<Result<R, Error>>>>;
+
+/// A typed handle to a task of a [Job]. Once the task has run, the handle
+/// gives out what it returned. Clones of a handle share the same result
+pub struct TaskHandle<R> {
+    id: usize,
+    result: SharedResult<R>,
+}
+
+/// One or more upstream tasks of a [Job], given by their handles, whose
+/// outputs are fed into a downstream task. Implemented for a [TaskHandle], and
+/// for tuples of up to four upstreams
+pub trait Upstream {
+    /// What the upstream tasks return, as a tuple if there are several
+    type Output;
+
+    /// The unique IDs of the upstream tasks
+    fn ids(&self) -> Vec<usize>;
+
+    /// Clones what the upstream tasks returned, if they all succeeded. The
+    /// results stay in the task handles
+    fn outputs(&self) -> Option<Self::Output>;
+}
+
+/// Runs a runnable after feeding it the outputs of its upstream tasks
+struct Fed<T, U, M> {
+    runnable: T,
+    upstream: U,
+    map: M,
+}
+
+/// What a [Job] needs from the runnable of a task: to run it, and to learn the
+/// error message if it fails
+trait Execute {
+    fn execute(&mut self) -> Result<(), String>;
+
+    /// Replaces what the runnable returned with an error raised by its task,
+    /// like a timeout, and gives the message of the error
+    fn fail(&mut self, error: Error) -> String;
+}
+
+/// Runs a runnable after giving it the cancellation token of its task
+struct WithToken<T> {
+    runnable: T,
+}
+
+/// Runs a runnable, and keeps what it returns for its [TaskHandle]
+struct Returning<T: RunAndReturn> {
+    runnable: T,
+    result: SharedResult<T::ReturnType>,
+}
+
+/// A task of a [Job], identified by a unique ID and a name
+struct Task {
+    id: usize,
+    name: String,
+    runnable: Box<dyn Execute + Send>,
+    timeout: Option<Duration>,
+    tags: Vec<String>,
+}
+
+/// Cancels the token of a task that is still running when its timeout is
+/// over. The timer stops when it is dropped
+struct TaskTimer {
+    timed_out: Arc<AtomicBool>,
+    _finished: mpsc::Sender<()>, // disconnects the timer thread when dropped
+}
+
+/// A batch of callables and commands. Tasks may depend on other tasks, and
+/// only run once all of their dependencies have succeeded. Finally tasks run
+/// after all other tasks, whatever happened to them
+///
+/// A job runs its tasks one at a time by default, in the order in which they
+/// were pushed unless their dependencies say otherwise, even when they are
+/// independent of each other. Nothing runs in parallel until it is asked for
+/// with [Job::parallel] or [Job::max_concurrency]. Then as many tasks as are
+/// ready, up to that limit, run at the same time on a pool of worker threads
 pub struct Job {
-    tasks: VecDeque<Box<dyn Run>>,
+    id: usize,
+    tasks: VecDeque<Task>,
+    finally_tasks: VecDeque<Task>,
+    dependencies: Vec<(Dependency, Dependency)>,
+    error_policy: ErrorPolicy,
+    max_concurrency: usize,
+    observers: Vec<Arc<dyn JobObserver>>,
+    cancellation_token: Option<CancellationToken>,
+    logging_format: Option<Arc<LoggingFormat>>,
+    #[cfg(feature = "signal_support")]
+    signal_grace_period: Option<Duration>,
+}
+
+/// The dependencies between the tasks of a [Job], by the index of each task
+struct TaskGraph {
+    dependents: Vec<Vec<usize>>,
+    dependency_counts: Vec<usize>,
+}
+
+/// Hands out the tasks of a [TaskGraph] as they become ready, and keeps track
+/// of what happened to each of them
+struct Scheduler<'c> {
+    graph: TaskGraph,
+    ready: BTreeSet<usize>,
+    reports: Vec<Option<TaskReport>>,
+    identities: Vec<(usize, String)>,
+    context: &'c TaskContext,
+}
+
+/// The state that a running [Job] shares with the task that is running on the
+/// current thread
+#[derive(Clone)]
+pub(crate) struct TaskContext {
+    cancellation_token: CancellationToken,
+    job_id: usize,
+    observers: Arc<Vec<Arc<dyn JobObserver>>>,
+    task: Option<(usize, String)>, // the ID and name of the running task
+    logging_format: Option<Arc<LoggingFormat>>,
+    #[cfg(feature = "signal_support")]
+    interruption: Option<Arc<Interruption>>,
+    #[cfg(feature = "tracing_support")]
+    span: tracing::Span, // the span of the job
+}
+
+thread_local! {
+    static CURRENT_TASK_CONTEXT: RefCell<Option<TaskContext>> = const { RefCell::new(None) };
+}
+
+// STRUCT IMPLEMENTATIONS
+
+impl Display for Dependency {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Dependency::Id(id) => write!(formatter, "#{}", id),
+            Dependency::Name(name) => write!(formatter, "{}", name),
+        }
+    }
+}
+
+impl From<usize> for Dependency {
+    fn from(id: usize) -> Self {
+        return Dependency::Id(id);
+    }
+}
+
+impl From<&str> for Dependency {
+    fn from(name: &str) -> Self {
+        return Dependency::Name(name.to_owned());
+    }
+}
+
+impl From<String> for Dependency {
+    fn from(name: String) -> Self {
+        return Dependency::Name(name);
+    }
+}
+
+impl<R> TaskHandle<R> {
+    /// The unique ID of the task
+    pub fn id(&self) -> usize {
+        return self.id;
+    }
+
+    /// Whether the task has finished running
+    pub fn is_finished(&self) -> bool {
+        return self.result.lock().unwrap().is_some();
+    }
+
+    /// Takes what the task returned, or the error it failed with. Returns
+    /// `None` if the task has not finished running yet, or was skipped, or if
+    /// the result was already taken
+    pub fn take(&self) -> Option<Result<R, Error>> {
+        return self.result.lock().unwrap().take();
+    }
+}
+
+impl<R> Clone for TaskHandle<R> {
+    fn clone(&self) -> Self {
+        return TaskHandle {
+            id: self.id,
+            result: self.result.clone(),
+        };
+    }
+}
+
+impl<R> From<&TaskHandle<R>> for Dependency {
+    fn from(task_handle: &TaskHandle<R>) -> Self {
+        return Dependency::Id(task_handle.id);
+    }
+}
+
+impl<R: Clone> Upstream for TaskHandle<R> {
+    type Output = R;
+
+    fn ids(&self) -> Vec<usize> {
+        return vec![self.id];
+    }
+
+    fn outputs(&self) -> Option<Self::Output> {
+        return match self.result.lock().unwrap().as_ref() {
+            Some(Ok(output)) => Some(output.clone()),
+            _ => None,
+        };
+    }
+}
+
+macro_rules! upstream_tuple {
+    ( $( $upstream:ident : $index:tt ),+ ) => {
+        impl<$( $upstream: Upstream ),+> Upstream for ( $( $upstream, )+ ) {
+            type Output = ( $( $upstream::Output, )+ );
+
+            fn ids(&self) -> Vec<usize> {
+                let mut ids = Vec::new();
+                $( ids.extend(self.$index.ids()); )+
+                return ids;
+            }
+
+            fn outputs(&self) -> Option<Self::Output> {
+                return Some(( $( self.$index.outputs()?, )+ ));
+            }
+        }
+    };
+}
+
+upstream_tuple!(U0: 0);
+upstream_tuple!(U0: 0, U1: 1);
+upstream_tuple!(U0: 0, U1: 1, U2: 2);
+upstream_tuple!(U0: 0, U1: 1, U2: 2, U3: 3);
+
+impl<T, U, M> RunAndReturn for Fed<T, U, M>
+where
+    T: RunAndReturn + AcceptInput,
+    U: Upstream,
+    M: FnMut(U::Output) -> T::Input,
+{
+    type ReturnType = T::ReturnType;
+
+    fn run_and_return(&mut self) -> Result<Self::ReturnType, Error> {
+        let outputs = self.upstream.outputs().context(UpstreamOutputMissing)?;
+        self.runnable.accept_input((self.map)(outputs));
+        return self.runnable.run_and_return();
+    }
+}
+
+impl<T: RunAndReturn> Execute for Returning<T> {
+    fn execute(&mut self) -> Result<(), String> {
+        let result = self.runnable.run_and_return();
+        let message = result.as_ref().err().map(|error| error.to_string());
+        *self.result.lock().unwrap() = Some(result);
+        return match message {
+            Some(message) => Err(message),
+            None => Ok(()),
+        };
+    }
+
+    fn fail(&mut self, error: Error) -> String {
+        let message = error.to_string();
+        *self.result.lock().unwrap() = Some(Err(error));
+        return message;
+    }
+}
+
+impl<T> RunAndReturn for WithToken<T>
+where
+    T: RunAndReturn + AcceptInput<Input = (CancellationToken,)>,
+{
+    type ReturnType = T::ReturnType;
+
+    fn run_and_return(&mut self) -> Result<Self::ReturnType, Error> {
+        self.runnable.accept_input((CancellationToken::current(),));
+        return self.runnable.run_and_return();
+    }
+}
+
+impl TaskContext {
+    /// Creates a context for the tasks of a job. Its cancellation token is a
+    /// child of the given token, so that the job can stop itself early
+    fn new(job_id: usize, observers: Vec<Arc<dyn JobObserver>>, token: &CancellationToken) -> Self {
+        return TaskContext {
+            cancellation_token: token.child_token(),
+            job_id,
+            observers: Arc::new(observers),
+            task: None,
+            logging_format: None,
+            #[cfg(feature = "signal_support")]
+            interruption: None,
+            #[cfg(feature = "tracing_support")]
+            span: tracing::Span::none(),
+        };
+    }
+
+    /// Creates the context of a single task of the job
+    fn for_task(&self, id: usize, name: &str) -> Self {
+        let mut context = self.clone();
+        context.task = Some((id, name.to_owned()));
+        return context;
+    }
+
+    /// Notifies every observer of the job of an event of the job
+    fn notify_job<C: Fn(&dyn JobObserver, &JobEvent)>(&self, callback: C) {
+        if self.observers.is_empty() {
+            return;
+        }
+        let event = JobEvent {
+            job_id: self.job_id,
+            timestamp: SystemTime::now(),
+        };
+        for observer in self.observers.iter() {
+            callback(observer.as_ref(), &event);
+        }
+    }
+
+    /// Notifies every observer of the job of an event of the given task
+    fn notify(&self, task_id: usize, name: &str, callback: impl Fn(&dyn JobObserver, &TaskEvent)) {
+        if self.observers.is_empty() {
+            return;
+        }
+        let event = TaskEvent {
+            job_id: self.job_id,
+            task_id,
+            name: name.to_owned(),
+            timestamp: SystemTime::now(),
+        };
+        for observer in self.observers.iter() {
+            callback(observer.as_ref(), &event);
+        }
+    }
+
+    /// Notifies every observer of the job of an event of the running task
+    pub(crate) fn notify_task<C: Fn(&dyn JobObserver, &TaskEvent)>(&self, callback: C) {
+        if let Some((task_id, name)) = self.task.as_ref() {
+            self.notify(*task_id, name, callback);
+        }
+    }
+
+    /// Whether anything observes the output lines of the running task
+    pub(crate) fn observes_output(&self) -> bool {
+        return self.task.is_some() && !self.observers.is_empty();
+    }
+
+    /// Passes a line written by a command of the running task to the observers
+    pub(crate) fn output_line(&self, stream: OutputStream, line: &str) {
+        self.notify_task(|observer, event| observer.output_line(event, stream, line));
+    }
+
+    /// The ID of the job
+    #[cfg(feature = "tracing_support")]
+    pub(crate) fn job_id(&self) -> usize {
+        return self.job_id;
+    }
+
+    /// The span of the job, that the spans of its tasks are opened in
+    #[cfg(feature = "tracing_support")]
+    pub(crate) fn span(&self) -> &tracing::Span {
+        return &self.span;
+    }
+
+    /// The logging format of the job, for the logged callables that do not have
+    /// one
+    pub(crate) fn logging_format(&self) -> Option<&Arc<LoggingFormat>> {
+        return self.logging_format.as_ref();
+    }
+
+    /// The context of the task running on the current thread, if it was started
+    /// by a job
+    pub(crate) fn current() -> Option<TaskContext> {
+        return CURRENT_TASK_CONTEXT.with(|context| context.borrow().clone());
+    }
+
+    /// Whether the task should stop early, because its job, or any job
+    /// containing it, was stopped
+    pub(crate) fn is_aborted(&self) -> bool {
+        return self.cancellation_token.is_cancelled();
+    }
+
+    /// Whether the job was interrupted by a signal, so that no more tasks
+    /// should be started
+    pub(crate) fn is_interrupted(&self) -> bool {
+        #[cfg(feature = "signal_support")]
+        return self
+            .interruption
+            .as_ref()
+            .is_some_and(|interruption| interruption.signal().is_some());
+        #[cfg(not(feature = "signal_support"))]
+        return false;
+    }
+
+    /// The signal that interrupted the job, if it handles signals and has been
+    /// interrupted
+    #[cfg(feature = "signal_support")]
+    pub(crate) fn interruption_signal(&self) -> Option<i32> {
+        return self
+            .interruption
+            .as_ref()
+            .and_then(|interruption| interruption.signal());
+    }
+
+    /// Tracks the process group led by a running command of the job, so that a
+    /// second signal kills it before the process exits
+    #[cfg(feature = "signal_support")]
+    pub(crate) fn track_process_group(&self, process_group: u32) -> Option<TrackedProcessGroup> {
+        return self
+            .interruption
+            .as_ref()
+            .map(|interruption| interruption.track(process_group));
+    }
+
+    /// Whether the commands of the job are started in process groups of their
+    /// own, so that signals can be forwarded to them
+    #[cfg(feature = "signal_support")]
+    pub(crate) fn handles_signals(&self) -> bool {
+        return self.interruption.is_some();
+    }
+
+    /// The token that is cancelled when the task should stop early
+    pub(crate) fn cancellation_token(&self) -> &CancellationToken {
+        return &self.cancellation_token;
+    }
+
+    fn abort(&self) {
+        self.cancellation_token.cancel();
+    }
+
+    /// Runs the given closure with this context as the current one
+    fn enter<T, C: FnOnce() -> T>(&self, callback: C) -> T {
+        let previous = CURRENT_TASK_CONTEXT.with(|context| context.replace(Some(self.clone())));
+        let output = callback();
+        CURRENT_TASK_CONTEXT.with(|context| context.replace(previous));
+        return output;
+    }
+}
+
+impl Task {
+    /// Creates a task, along with the handle to its return value
+    fn new<T>(id: usize, name: String, runnable: T) -> (Self, TaskHandle<T::ReturnType>)
+    where
+        T: RunAndReturn + Send + 'static,
+        T::ReturnType: Send + 'static,
+    {
+        let result = Arc::new(Mutex::new(None));
+        let task = Task {
+            id,
+            name,
+            runnable: Box::new(Returning {
+                runnable,
+                result: result.clone(),
+            }),
+            timeout: None,
+            tags: Vec::new(),
+        };
+        return (task, TaskHandle { id, result });
+    }
+
+    /// Runs the task within the given context, and reports what happened
+    fn run(&mut self, job_context: &TaskContext, error_policy: ErrorPolicy) -> TaskReport {
+        let mut context = job_context.for_task(self.id, &self.name);
+        let timer = self.timeout.map(|timeout| {
+            // A task that times out must not stop the rest of the job
+            context.cancellation_token = context.cancellation_token.child_token();
+            return TaskTimer::start(timeout, context.cancellation_token.clone());
+        });
+        context.notify_task(|observer, event| observer.task_started(event));
+        let task_start = Instant::now();
+        #[cfg(feature = "tracing_support")]
+        let traced_run = TracedRun::start(trace::task_span(&context, self.id, &self.name));
+        #[cfg(feature = "tracing_support")]
+        let _entered = traced_run.span().enter();
+        let outcome = match context.enter(|| self.runnable.execute()) {
+            _ if timer.as_ref().is_some_and(TaskTimer::timed_out) => {
+                let timed_out = TaskTimedOut {
+                    task: self.name.clone(),
+                    timeout: self.timeout.unwrap_or_default(),
+                };
+                TaskOutcome::Failed(self.runnable.fail(timed_out.build().into()))
+            }
+            Ok(()) => TaskOutcome::Succeeded,
+            Err(_message) if context.is_aborted() || context.is_interrupted() => {
+                TaskOutcome::Cancelled
+            }
+            Err(message) => TaskOutcome::Failed(message),
+        };
+        if let TaskOutcome::Failed(_) = outcome {
+            if error_policy == ErrorPolicy::StopAtFirstFailure {
+                job_context.abort();
+            }
+        }
+        context.notify_task(|observer, event| {
+            match &outcome {
+                TaskOutcome::Succeeded => observer.task_succeeded(event),
+                TaskOutcome::Failed(message) => observer.task_failed(event, message),
+                TaskOutcome::Cancelled => observer.task_cancelled(event),
+                TaskOutcome::Skipped => observer.task_skipped(event),
+            }
+        });
+        #[cfg(feature = "tracing_support")]
+        match &outcome {
+            TaskOutcome::Failed(message) => traced_run.complete("failed", Some(message)),
+            TaskOutcome::Succeeded => traced_run.complete("succeeded", None),
+            TaskOutcome::Cancelled => traced_run.complete("cancelled", None),
+            TaskOutcome::Skipped => traced_run.complete("skipped", None),
+        }
+        return TaskReport {
+            id: self.id,
+            name: self.name.clone(),
+            outcome,
+            duration: task_start.elapsed(),
+            tags: self.tags.clone(),
+        };
+    }
+
+    /// Runs the task like [Task::run], but reports a panic that escapes the
+    /// runnable, such as one of an observer or of a hand-written
+    /// [RunAndReturn], as a failure of the task instead of unwinding. A panic
+    /// must not take a worker of a parallel job down, as the job would then
+    /// wait forever for the report of the task
+    fn run_catching_panics(
+        &mut self,
+        job_context: &TaskContext,
+        error_policy: ErrorPolicy,
+    ) -> TaskReport {
+        let task_start = Instant::now();
+        let outer_context = TaskContext::current();
+        let result = panic::catch_unwind(AssertUnwindSafe(|| self.run(job_context, error_policy)));
+        return result.unwrap_or_else(|payload| {
+            // The panic skipped restoring the context of the thread
+            CURRENT_TASK_CONTEXT.with(|context| context.replace(outer_context));
+            if error_policy == ErrorPolicy::StopAtFirstFailure {
+                job_context.abort();
+            }
+            return TaskReport {
+                id: self.id,
+                name: self.name.clone(),
+                outcome: TaskOutcome::Failed(format!(
+                    "Task panicked: {}",
+                    panic_message(payload.as_ref())
+                )),
+                duration: task_start.elapsed(),
+                tags: self.tags.clone(),
+            };
+        });
+    }
+}
+
+impl TaskTimer {
+    /// Starts a timer that cancels the token once the timeout is over
+    fn start(timeout: Duration, token: CancellationToken) -> Self {
+        let timed_out = Arc::new(AtomicBool::new(false));
+        let (finished, finished_receiver) = mpsc::channel::<()>();
+        let timer_timed_out = timed_out.clone();
+        thread::spawn(move || {
+            if let Err(mpsc::RecvTimeoutError::Timeout) = finished_receiver.recv_timeout(timeout) {
+                timer_timed_out.store(true, Ordering::SeqCst);
+                token.cancel();
+            }
+        });
+        return TaskTimer {
+            timed_out,
+            _finished: finished,
+        };
+    }
+
+    /// Whether the timeout was over before the task finished
+    fn timed_out(&self) -> bool {
+        return self.timed_out.load(Ordering::SeqCst);
+    }
+}
+
+impl TaskGraph {
+    /// The indices of the tasks that take part in a dependency cycle, in the
+    /// order of the cycle, if there is one
+    fn find_cycle(&self) -> Option<Vec<usize>> {
+        // Kahn's algorithm: whatever cannot be sorted topologically is in or
+        // downstream of a cycle
+        let mut dependency_counts = self.dependency_counts.clone();
+        let mut sorted: Vec<usize> = (0..dependency_counts.len())
+            .filter(|&index| dependency_counts[index] == 0)
+            .collect();
+        let mut cursor = 0;
+        while cursor < sorted.len() {
+            for &dependent in &self.dependents[sorted[cursor]] {
+                dependency_counts[dependent] -= 1;
+                if dependency_counts[dependent] == 0 {
+                    sorted.push(dependent);
+                }
+            }
+            cursor += 1;
+        }
+        let unsorted: Vec<usize> = (0..dependency_counts.len())
+            .filter(|&index| dependency_counts[index] > 0)
+            .collect();
+        let start = *unsorted.first()?;
+
+        // Every unsorted task has an unsorted dependency, so following those
+        // must eventually repeat a task
+        let mut dependencies: Vec<Vec<usize>> = vec![Vec::new(); self.dependents.len()];
+        for (index, dependents) in self.dependents.iter().enumerate() {
+            for &dependent in dependents {
+                dependencies[dependent].push(index);
+            }
+        }
+        let mut path = vec![start];
+        loop {
+            let current = *path.last().unwrap();
+            let next = *dependencies[current]
+                .iter()
+                .find(|&&dependency| dependency_counts[dependency] > 0)
+                .unwrap();
+            if let Some(position) = path.iter().position(|&index| index == next) {
+                let mut cycle = path.split_off(position);
+                cycle.push(*cycle.first().unwrap());
+                return Some(cycle);
+            }
+            path.push(next);
+        }
+    }
+}
+
+impl<'c> Scheduler<'c> {
+    fn new(graph: TaskGraph, identities: Vec<(usize, String)>, context: &'c TaskContext) -> Self {
+        let mut scheduler = Scheduler {
+            ready: BTreeSet::new(),
+            reports: vec![None; identities.len()],
+            graph,
+            identities,
+            context,
+        };
+        for index in 0..scheduler.identities.len() {
+            if scheduler.graph.dependency_counts[index] == 0 {
+                scheduler.queue(index);
+            }
+        }
+        return scheduler;
+    }
+
+    /// Marks a task as ready to run
+    fn queue(&mut self, index: usize) {
+        let (id, name) = &self.identities[index];
+        self.context
+            .notify(*id, name, |observer, event| observer.task_queued(event));
+        self.ready.insert(index);
+    }
+
+    /// The index of the next task to run, preferring tasks that were pushed
+    /// earlier. Nothing is handed out once the job has been stopped
+    fn next_ready(&mut self) -> Option<usize> {
+        if self.context.is_aborted() || self.context.is_interrupted() {
+            return None;
+        }
+        let index = *self.ready.iter().next()?;
+        self.ready.remove(&index);
+        return Some(index);
+    }
+
+    /// Records what happened to a task. Only the dependents of a successful
+    /// task can become ready
+    fn complete(&mut self, index: usize, report: TaskReport) {
+        if report.outcome == TaskOutcome::Succeeded {
+            for dependent_position in 0..self.graph.dependents[index].len() {
+                let dependent = self.graph.dependents[index][dependent_position];
+                self.graph.dependency_counts[dependent] -= 1;
+                if self.graph.dependency_counts[dependent] == 0 {
+                    self.queue(dependent);
+                }
+            }
+        }
+        self.reports[index] = Some(report);
+    }
+
+    /// Reports the tasks that never ran as cancelled if the job was cancelled,
+    /// or as skipped otherwise
+    fn into_reports(self, cancelled: bool) -> Vec<TaskReport> {
+        let context = self.context;
+        return self
+            .reports
+            .into_iter()
+            .zip(self.identities)
+            .map(|(report, (id, name))| {
+                report.unwrap_or_else(|| {
+                    let outcome = match cancelled {
+                        true => {
+                            context.notify(id, &name, |observer, event| {
+                                observer.task_cancelled(event)
+                            });
+                            TaskOutcome::Cancelled
+                        }
+                        false => {
+                            context
+                                .notify(id, &name, |observer, event| observer.task_skipped(event));
+                            TaskOutcome::Skipped
+                        }
+                    };
+                    TaskReport {
+                        id,
+                        name,
+                        outcome,
+                        duration: Duration::default(),
+                        tags: Vec::new(), // filled in by the job
+                    }
+                })
+            })
+            .collect();
+    }
+}
+
+impl JobReport {
+    /// Whether every task of the job succeeded
+    pub fn succeeded(&self) -> bool {
+        return self.succeeded_count() == self.tasks.len();
+    }
+
+    /// The number of tasks that succeeded
+    pub fn succeeded_count(&self) -> usize {
+        return self
+            .tasks
+            .iter()
+            .filter(|task| task.outcome == TaskOutcome::Succeeded)
+            .count();
+    }
+
+    /// The reports of the tasks that failed
+    pub fn failures(&self) -> impl Iterator<Item = &TaskReport> {
+        return self
+            .tasks
+            .iter()
+            .filter(|task| matches!(task.outcome, TaskOutcome::Failed(_)));
+    }
+
+    /// The reports of the tasks with the given tag
+    pub fn tagged<'r>(&'r self, tag: &'r str) -> impl Iterator<Item = &'r TaskReport> {
+        return self
+            .tasks
+            .iter()
+            .filter(move |task| task.tags.iter().any(|task_tag| task_tag == tag));
+    }
+}
+
+impl Job {
+    /// Creates a new job with no tasks, that stops at the first failure
+    pub fn new() -> Self {
+        return Job {
+            id: generate_task_id(),
+            tasks: VecDeque::new(),
+            finally_tasks: VecDeque::new(),
+            dependencies: Vec::new(),
+            error_policy: ErrorPolicy::default(),
+            max_concurrency: 1,
+            observers: Vec::new(),
+            cancellation_token: None,
+            logging_format: None,
+            #[cfg(feature = "signal_support")]
+            signal_grace_period: None,
+        };
+    }
+
+    /// Sets what the job does when one of its tasks fails. When running in
+    /// parallel, stopping at the first failure also kills the commands of the
+    /// sibling tasks that are still running
+    pub fn error_policy(mut self, error_policy: ErrorPolicy) -> Self {
+        self.error_policy = error_policy;
+        return self;
+    }
+
+    /// Runs independent tasks in parallel, as many at a time as there are
+    /// CPUs available
+    pub fn parallel(self) -> Self {
+        let available_cpus = thread::available_parallelism().map_or(1, |cpus| cpus.get());
+        return self.max_concurrency(available_cpus);
+    }
+
+    /// Sets the maximum number of tasks that may run at the same time. A value
+    /// of 1 (the default) runs the tasks one by one
+    pub fn max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = max_concurrency.max(1);
+        return self;
+    }
+
+    /// Attaches an observer that is notified of the lifecycle events of the job
+    /// and its tasks. Several observers can be attached
+    pub fn observer<O: JobObserver + 'static>(mut self, observer: O) -> Self {
+        self.observers.push(Arc::new(observer));
+        return self;
+    }
+
+    /// Sets a token through which the job can be cancelled while it runs. The
+    /// job checks the token between tasks, and kills the commands of the tasks
+    /// that are running when it fires. Tasks that are stopped or never run are
+    /// reported as cancelled
+    pub fn cancellation_token(mut self, token: CancellationToken) -> Self {
+        self.cancellation_token = Some(token);
+        return self;
+    }
+
+    /// Sets the format in which the logged callables of the job log their runs,
+    /// unless they have a format of their own. Jobs nested in the tasks of this
+    /// job use it too, unless they also have a format
+    pub fn logging_format<L: Into<Arc<LoggingFormat>>>(mut self, logging_format: L) -> Self {
+        self.logging_format = Some(logging_format.into());
+        return self;
+    }
+
+    /// Handles SIGINT and SIGTERM while the job runs (Unix only). The first
+    /// signal stops the job from starting more tasks, and is forwarded to the
+    /// running commands, which run in process groups of their own. Commands
+    /// still running after the grace period are killed. Finally tasks are then
+    /// run, and the report is marked as interrupted. A second signal exits the
+    /// process immediately
+    #[cfg(feature = "signal_support")]
+    pub fn handle_signals(mut self, grace_period: Duration) -> Self {
+        self.signal_grace_period = Some(grace_period);
+        return self;
+    }
+
+    /// The unique ID of the job
+    pub fn id(&self) -> usize {
+        return self.id;
+    }
+
+    /// Appends a callable, a command, or any other runnable to the job, and
+    /// returns a handle through which its return value can be retrieved
+    pub fn push<T>(&mut self, runnable: T) -> TaskHandle<T::ReturnType>
+    where
+        T: RunAndReturn + Send + 'static,
+        T::ReturnType: Send + 'static,
+    {
+        let id = generate_task_id();
+        return self.push_task(id, format!("task-{}", id), runnable);
+    }
+
+    /// Does what [Job::push] does, but also gives the task a name for reports
+    /// and dependencies
+    pub fn push_named<S, T>(&mut self, name: S, runnable: T) -> TaskHandle<T::ReturnType>
+    where
+        S: Into<String>,
+        T: RunAndReturn + Send + 'static,
+        T::ReturnType: Send + 'static,
+    {
+        return self.push_task(generate_task_id(), name.into(), runnable);
+    }
+
+    /// Pushes a task that describes itself, like a struct that derives `Task`,
+    /// under its own name
+    pub fn push_described<T>(&mut self, runnable: T) -> TaskHandle<T::ReturnType>
+    where
+        T: DescribeTask + RunAndReturn + Send + 'static,
+        T::ReturnType: Send + 'static,
+    {
+        let name = runnable.task_name();
+        return self.push_task(generate_task_id(), name, runnable);
+    }
+
+    fn push_task<T>(&mut self, id: usize, name: String, runnable: T) -> TaskHandle<T::ReturnType>
+    where
+        T: RunAndReturn + Send + 'static,
+        T::ReturnType: Send + 'static,
+    {
+        let (task, task_handle) = Task::new(id, name, runnable);
+        self.tasks.push_back(task);
+        return task_handle;
+    }
+
+    /// Appends a cleanup task, that runs after all other tasks, even if they
+    /// failed or the job was cancelled or interrupted. Finally tasks run one
+    /// by one, in the order in which they were pushed, and cannot take part in
+    /// dependencies
+    pub fn push_finally<S, T>(&mut self, name: S, runnable: T) -> TaskHandle<T::ReturnType>
+    where
+        S: Into<String>,
+        T: RunAndReturn + Send + 'static,
+        T::ReturnType: Send + 'static,
+    {
+        let id = generate_task_id();
+        let (task, task_handle) = Task::new(id, name.into(), runnable);
+        self.finally_tasks.push_back(task);
+        return task_handle;
+    }
+
+    /// Appends a task that receives the cancellation token of its task as its
+    /// only input, just before it runs. For example, a callable that takes a
+    /// [CancellationToken] as its argument can poll it to stop early
+    pub fn push_cancellable<S, T>(&mut self, name: S, runnable: T) -> TaskHandle<T::ReturnType>
+    where
+        S: Into<String>,
+        T: RunAndReturn + AcceptInput<Input = (CancellationToken,)> + Send + 'static,
+        T::ReturnType: Send + 'static,
+    {
+        return self.push_task(generate_task_id(), name.into(), WithToken { runnable });
+    }
+
+    /// Appends a task whose input is what its upstream tasks return. The task
+    /// depends on its upstream tasks, so it only runs once they all succeed.
+    /// For example, a callable can receive its arguments from the upstreams
+    pub fn push_fed<S, T, U>(
+        &mut self,
+        name: S,
+        runnable: T,
+        upstream: U,
+    ) -> TaskHandle<T::ReturnType>
+    where
+        S: Into<String>,
+        T: RunAndReturn + AcceptInput + Send + 'static,
+        T::ReturnType: Send + 'static,
+        U: Upstream<Output = T::Input> + Send + 'static,
+    {
+        return self.push_fed_with(name, runnable, upstream, |outputs| outputs);
+    }
+
+    /// Does what [Job::push_fed] does, but maps the outputs of the upstream
+    /// tasks to the input of the task through a closure. For example, the
+    /// standard output of a command can become the arguments of another
+    pub fn push_fed_with<S, T, U, M>(
+        &mut self,
+        name: S,
+        runnable: T,
+        upstream: U,
+        map: M,
+    ) -> TaskHandle<T::ReturnType>
+    where
+        S: Into<String>,
+        T: RunAndReturn + AcceptInput + Send + 'static,
+        T::ReturnType: Send + 'static,
+        U: Upstream + Send + 'static,
+        M: FnMut(U::Output) -> T::Input + Send + 'static,
+    {
+        let upstream_ids = upstream.ids();
+        let task_handle = self.push_task(
+            generate_task_id(),
+            name.into(),
+            Fed {
+                runnable,
+                upstream,
+                map,
+            },
+        );
+        for upstream_id in upstream_ids {
+            self.depends_on(task_handle.id, upstream_id);
+        }
+        return task_handle;
+    }
+
+    /// Makes a task wait for another task, and only run if that task succeeds.
+    /// Either task can be given by its handle, its ID, or its name.
+    /// Dependencies are checked when the job is run
+    pub fn depends_on<T, D>(&mut self, task: T, dependency: D) -> &mut Self
+    where
+        T: Into<Dependency>,
+        D: Into<Dependency>,
+    {
+        self.dependencies.push((task.into(), dependency.into()));
+        return self;
+    }
+
+    /// Fails a task that is still running after the given duration. Its
+    /// cancellation token is cancelled, which kills its commands, but callables
+    /// have to poll the token themselves, and are failed once they return
+    pub fn timeout<R>(&mut self, task: &TaskHandle<R>, timeout: Duration) -> &mut Self {
+        if let Some(task) = self.task_mut(task.id) {
+            task.timeout = Some(timeout);
+        }
+        return self;
+    }
+
+    /// Tags a task, so that its report can be found with [JobReport::tagged]
+    pub fn tag<R, S: Into<String>>(&mut self, task: &TaskHandle<R>, tag: S) -> &mut Self {
+        if let Some(task) = self.task_mut(task.id) {
+            task.tags.push(tag.into());
+        }
+        return self;
+    }
+
+    /// The task or finally task with the given ID
+    fn task_mut(&mut self, id: usize) -> Option<&mut Task> {
+        return self
+            .tasks
+            .iter_mut()
+            .chain(self.finally_tasks.iter_mut())
+            .find(|task| task.id == id);
+    }
+
+    /// Checks that every dependency refers to exactly one task, and that no
+    /// tasks depend on each other in a cycle
+    pub fn validate(&self) -> Result<(), Error> {
+        return self.graph().map(|_graph| ());
+    }
+
+    /// Resolves the dependencies between the tasks into a graph
+    fn graph(&self) -> Result<TaskGraph, Error> {
+        let mut indices_by_id = HashMap::new();
+        let mut indices_by_name: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (index, task) in self.tasks.iter().enumerate() {
+            indices_by_id.insert(task.id, index);
+            indices_by_name
+                .entry(task.name.as_str())
+                .or_default()
+                .push(index);
+        }
+        // Finds the indices of all tasks that a dependency may refer to
+        let resolve = |dependency: &Dependency| -> Vec<usize> {
+            return match dependency {
+                Dependency::Id(id) => indices_by_id.get(id).into_iter().copied().collect(),
+                Dependency::Name(name) => {
+                    indices_by_name
+                        .get(name.as_str())
+                        .cloned()
+                        .unwrap_or_default()
+                }
+            };
+        };
+
+        let mut graph = TaskGraph {
+            dependents: vec![Vec::new(); self.tasks.len()],
+            dependency_counts: vec![0; self.tasks.len()],
+        };
+        for (task, dependency) in &self.dependencies {
+            let index = match resolve(task).as_slice() {
+                [index] => *index,
+                [] => {
+                    return TaskMissing {
+                        task: task.clone(),
+                        job_id: self.id,
+                    }
+                    .fail()
+                    .map_err(Error::from);
+                }
+                _ => {
+                    return TaskAmbiguous {
+                        task: task.clone(),
+                        job_id: self.id,
+                    }
+                    .fail()
+                    .map_err(Error::from);
+                }
+            };
+            let task = &self.tasks[index].name;
+            let dependency_index = match resolve(dependency).as_slice() {
+                [dependency_index] => *dependency_index,
+                [] => {
+                    return DependencyMissing {
+                        task,
+                        dependency: dependency.clone(),
+                    }
+                    .fail()
+                    .map_err(Error::from);
+                }
+                _ => {
+                    return DependencyAmbiguous {
+                        task,
+                        dependency: dependency.clone(),
+                    }
+                    .fail()
+                    .map_err(Error::from);
+                }
+            };
+            if !graph.dependents[dependency_index].contains(&index) {
+                graph.dependents[dependency_index].push(index);
+                graph.dependency_counts[index] += 1;
+            }
+        }
+
+        if let Some(cycle) = graph.find_cycle() {
+            let cycle: Vec<String> = cycle
+                .into_iter()
+                .map(|index| self.tasks[index].name.clone())
+                .collect();
+            return DependencyCycle { cycle }.fail().map_err(Error::from);
+        }
+        return Ok(graph);
+    }
+
+    /// Runs the tasks in an order that respects their dependencies, following
+    /// the error policy, and reports what happened to each of them. The report
+    /// is returned even when tasks fail. An error is only returned when the
+    /// dependencies are invalid, in which case no task is run
+    pub fn run_and_report(&mut self) -> Result<JobReport, Error> {
+        let graph = self.graph()?;
+        let job_start = Instant::now();
+        // Cancelling either the token of the job, or that of the task running
+        // the job when jobs are nested, cancels the job
+        let parent_context = TaskContext::current();
+        let job_token = CancellationToken::child_of(
+            self.cancellation_token.iter().chain(
+                parent_context
+                    .as_ref()
+                    .map(|context| context.cancellation_token()),
+            ),
+        );
+        let logging_format = self.logging_format.clone().or_else(|| {
+            parent_context
+                .as_ref()
+                .and_then(|context| context.logging_format.clone())
+        });
+        let mut context = TaskContext::new(self.id, self.observers.clone(), &job_token);
+        context.logging_format = logging_format.clone();
+        #[cfg(feature = "tracing_support")]
+        let traced_run = TracedRun::start(trace::job_span(
+            self.id,
+            parent_context.as_ref().map(|context| context.job_id),
+        ));
+        #[cfg(feature = "tracing_support")]
+        {
+            context.span = traced_run.span().clone();
+        }
+        #[cfg(feature = "signal_support")]
+        if let Some(grace_period) = self.signal_grace_period {
+            let token = context.cancellation_token().clone();
+            context.interruption = Some(Interruption::register(grace_period, token)?);
+        }
+        context.notify_job(|observer, event| observer.job_started(event));
+        let error_policy = self.error_policy;
+        let max_concurrency = self.max_concurrency;
+        let identities = self
+            .tasks
+            .iter()
+            .map(|task| (task.id, task.name.clone()))
+            .collect();
+        let mut scheduler = Scheduler::new(graph, identities, &context);
+        let mut tasks: Vec<Option<&mut Task>> = self.tasks.iter_mut().map(Some).collect();
+
+        if max_concurrency == 1 {
+            while let Some(index) = scheduler.next_ready() {
+                let task = tasks[index].take().unwrap();
+                scheduler.complete(index, task.run_catching_panics(&context, error_policy));
+            }
+        } else {
+            let (task_sender, task_receiver) = mpsc::channel::<(usize, &mut Task)>();
+            let task_receiver = Mutex::new(task_receiver);
+            let (report_sender, report_receiver) = mpsc::channel();
+            thread::scope(|scope| {
+                for _ in 0..max_concurrency.min(tasks.len()) {
+                    let (task_receiver, context) = (&task_receiver, &context);
+                    let report_sender = report_sender.clone();
+                    scope.spawn(move || {
+                        loop {
+                            let next = task_receiver.lock().unwrap().recv();
+                            let (index, task) = match next {
+                                Ok(next) => next,
+                                Err(_) => break,
+                            };
+                            let _ = report_sender
+                                .send((index, task.run_catching_panics(context, error_policy)));
+                        }
+                    });
+                }
+
+                let mut running = 0;
+                loop {
+                    while running < max_concurrency {
+                        match scheduler.next_ready() {
+                            Some(index) => {
+                                let task = tasks[index].take().unwrap();
+                                task_sender.send((index, task)).unwrap();
+                                running += 1;
+                            }
+                            None => break,
+                        }
+                    }
+                    if running == 0 {
+                        break;
+                    }
+                    let (index, report) = report_receiver.recv().unwrap();
+                    scheduler.complete(index, report);
+                    running -= 1;
+                }
+                drop(task_sender);
+            });
+        }
+
+        let cancelled = job_token.is_cancelled();
+        let interrupted = context.is_interrupted();
+        let mut task_reports = scheduler.into_reports(cancelled || interrupted);
+        for (task_report, task) in task_reports.iter_mut().zip(self.tasks.iter()) {
+            task_report.tags = task.tags.clone();
+        }
+        // Cleanup must not be stopped by whatever stopped the other tasks
+        let mut finally_context =
+            TaskContext::new(self.id, self.observers.clone(), &CancellationToken::new());
+        finally_context.logging_format = logging_format;
+        #[cfg(feature = "tracing_support")]
+        {
+            finally_context.span = context.span.clone();
+        }
+        for task in self.finally_tasks.iter_mut() {
+            task_reports
+                .push(task.run_catching_panics(&finally_context, ErrorPolicy::ContinueAndCollect));
+        }
+        let report = JobReport {
+            id: self.id,
+            tasks: task_reports,
+            duration: job_start.elapsed(),
+            cancelled,
+            interrupted,
+        };
+        context.notify_job(|observer, event| observer.job_finished(event, &report));
+        #[cfg(feature = "tracing_support")]
+        match (
+            report.interrupted,
+            report.cancelled,
+            report.failures().count(),
+        ) {
+            (true, _, _) => traced_run.complete("interrupted", None),
+            (_, true, _) => traced_run.complete("cancelled", None),
+            (_, _, 0) => traced_run.complete("succeeded", None),
+            (_, _, failures) => {
+                traced_run.complete("failed", Some(&format!("{} tasks failed", failures)))
+            }
+        }
+        return Ok(report);
+    }
+}
+
+impl Default for Job {
+    fn default() -> Self {
+        return Job::new();
+    }
+}
+
+// TRAIT IMPLEMENTATIONS
+
+impl RunAndReturn for Job {
+    type ReturnType = JobReport;
+
+    fn run_and_return(&mut self) -> Result<Self::ReturnType, Error> {
+        let report = self.run_and_report()?;
+        if report.interrupted {
+            return JobInterrupted { report }.fail().map_err(Error::from);
+        }
+        if report.cancelled {
+            return JobCancelled { report }.fail().map_err(Error::from);
+        }
+        if report.succeeded() || self.error_policy == ErrorPolicy::ContinueAndCollect {
+            return Ok(report);
+        }
+        return JobFailed { report }.fail().map_err(Error::from);
+    }
+}
+
+impl Run for Job {
+    fn run(&mut self) -> Result<(), Error> {
+        return self.run_and_return().map(|_report| ());
+    }
+}
+
+// TESTS
+
+#[cfg(test)]
+mod tests {
+
+    // IMPORTS
+
+    use super::{ErrorPolicy, Job, JobError, JobReport, TaskOutcome};
+    use crate::callable::{Callable, LoggedCallable, LoggingFormat};
+    use crate::cancellation::CancellationToken;
+    use crate::instruction::{Command, CommandInput};
+    use crate::observer::{JobEvent, JobObserver, LogObserver, OutputStream, TaskEvent};
+    use crate::retry::Retry;
+    use crate::tests::capture_logs;
+    use crate::{task, DescribeTask, Error, ErrorCode, Run, RunAndReturn, RunningError, Task};
+    use std::sync::{mpsc, Arc, Mutex};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    // FUNCTIONS
+
+    #[task(name = "doubling", retries = 1, tags = ["math"], depends_on = ["start"])]
+    fn double(value: i32) -> i32 {
+        return value * 2;
+    }
+
+    #[task(level = log::Level::Debug)]
+    fn greet(name: String, times: usize) -> String {
+        return name.repeat(times);
+    }
+
+    /// A type that [crate::Represent] is not implemented for
+    #[derive(Debug)]
+    struct Rectangle {
+        width: u32,
+        height: u32,
+    }
+
+    #[task]
+    fn area(rectangle: Rectangle) -> u32 {
+        return rectangle.width * rectangle.height;
+    }
+
+    #[task(timeout = Duration::from_millis(50), tags = ["slow", "math"])]
+    fn sleep() -> bool {
+        return Command::new("sleep").arg("5").run().is_ok();
+    }
+
+    /// Counts up to a limit, failing on the attempts before it
+    #[derive(Task)]
+    #[running(run = count, returns = usize, fallible, name = "counting")]
+    struct Count {
+        limit: usize,
+        label: &'static str,
+        #[running(skip)]
+        attempts: usize,
+    }
+
+    impl Count {
+        fn count(&mut self) -> Result<usize, std::io::Error> {
+            self.attempts += 1;
+            if self.attempts < self.limit {
+                let message = format!("attempt {} of {}", self.attempts, self.limit);
+                return Err(std::io::Error::other(message));
+            }
+            return Ok(self.attempts);
+        }
+    }
+
+    /// Panics outside of any callable, where nothing else catches the panic
+    struct Panicking;
+
+    impl RunAndReturn for Panicking {
+        type ReturnType = ();
+
+        fn run_and_return(&mut self) -> Result<(), Error> {
+            panic!("Panicking test...");
+        }
+    }
+
+    /// Creates a job of three callables, with a failing command after the
+    /// second
+    fn failing_job(error_policy: ErrorPolicy, order: &Arc<Mutex<Vec<usize>>>) -> Job {
+        let mut job = Job::new().error_policy(error_policy);
+        for index in 0..3 {
+            let order = order.clone();
+            job.push(Callable::new(move || order.lock().unwrap().push(index)));
+            if index == 1 {
+                job.push_named("failing", Command::new("false"));
+            }
+        }
+        return job;
+    }
+
+    // TESTS
+
+    #[test]
+    fn sequential_order() {
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let mut job = Job::new();
+        for index in 0..3 {
+            let order = order.clone();
+            job.push(Callable::new(move || order.lock().unwrap().push(index)));
+        }
+        job.push_named("echo", Command::new("echo").arg("Hello"));
+
+        let report = job.run_and_return().unwrap();
+        assert!(report.succeeded());
+        assert_eq!(report.tasks.len(), 4);
+        assert_eq!(report.tasks[3].name, "echo");
+        assert_eq!(*order.lock().unwrap(), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn stop_at_first_failure() {
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let mut job = failing_job(ErrorPolicy::StopAtFirstFailure, &order);
+
+        let error = job.run_and_return().unwrap_err();
+        assert_eq!(*order.lock().unwrap(), vec![0, 1]);
+        match &error {
+            RunningError::Job(JobError::JobFailed { report, .. }) => {
+                assert_eq!(report.tasks[2].name, "failing");
+                assert_eq!(report.tasks[3].outcome, TaskOutcome::Skipped);
+            }
+            _ => panic!("Expected a job failure"),
+        }
+    }
+
+    #[test]
+    fn continue_and_collect() {
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let mut job = failing_job(ErrorPolicy::ContinueAndCollect, &order);
+
+        let report = job.run_and_return().unwrap();
+        assert_eq!(*order.lock().unwrap(), vec![0, 1, 2]);
+        assert_eq!(report.failures().count(), 1);
+        assert_eq!(report.failures().next().unwrap().name, "failing");
+    }
+
+    #[test]
+    fn fail_at_end() {
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let mut job = failing_job(ErrorPolicy::FailAtEnd, &order);
+
+        assert!(job.run_and_return().is_err());
+        assert_eq!(*order.lock().unwrap(), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn parallel() {
+        let mut job = Job::new().max_concurrency(4);
+        for _ in 0..4 {
+            job.push(Callable::new(|| thread::sleep(Duration::from_millis(200))));
+        }
+
+        let start = Instant::now();
+        let report = job.run_and_return().unwrap();
+        assert!(start.elapsed() < Duration::from_millis(600));
+        assert_eq!(report.tasks.len(), 4);
+    }
+
+    #[test]
+    fn parallel_panic() {
+        let (report_sender, report_receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let mut job = Job::new()
+                .max_concurrency(2)
+                .error_policy(ErrorPolicy::ContinueAndCollect);
+            job.push_named("panicking", Panicking);
+            job.push_named(
+                "sleeping",
+                Callable::new(|| thread::sleep(Duration::from_millis(100))),
+            );
+            let _ = report_sender.send(job.run_and_report().unwrap());
+        });
+
+        let report = report_receiver
+            .recv_timeout(Duration::from_secs(5))
+            .unwrap();
+        assert_eq!(
+            report.tasks[0].outcome,
+            TaskOutcome::Failed(String::from("Task panicked: Panicking test..."))
+        );
+        assert_eq!(report.tasks[1].outcome, TaskOutcome::Succeeded);
+    }
+
+    #[test]
+    fn parallel_fail_fast() {
+        let mut job = Job::new().max_concurrency(2);
+        job.push_named("slow", Command::new("sleep").arg("5"));
+        job.push_named(
+            "failing",
+            Command::new("sh").args(["-c", "sleep 0.2; exit 1"]),
+        );
+        job.push_named("queued", Command::new("true"));
+
+        let start = Instant::now();
+        let report = job.run_and_report().unwrap();
+        assert!(start.elapsed() < Duration::from_secs(3));
+        assert_eq!(report.tasks[0].outcome, TaskOutcome::Cancelled);
+        assert!(matches!(report.tasks[1].outcome, TaskOutcome::Failed(_)));
+        assert_eq!(report.tasks[2].outcome, TaskOutcome::Skipped);
+    }
+
+    #[test]
+    fn dependency_order() {
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let mut job = Job::new().max_concurrency(2);
+        let push = |job: &mut Job, name: &'static str| {
+            let order = order.clone();
+            job.push_named(
+                name,
+                Callable::new(move || order.lock().unwrap().push(name)),
+            )
+        };
+        let test = push(&mut job, "test");
+        let build = push(&mut job, "build");
+        let fetch = push(&mut job, "fetch");
+        job.depends_on(&test, &build).depends_on("build", "fetch");
+        job.depends_on(test.id(), fetch.id());
+
+        assert!(job.run_and_return().unwrap().succeeded());
+        assert_eq!(*order.lock().unwrap(), vec!["fetch", "build", "test"]);
+    }
+
+    #[test]
+    fn dependency_failure_skips_downstream_only() {
+        let mut job = Job::new().error_policy(ErrorPolicy::ContinueAndCollect);
+        let build = job.push_named("build", Command::new("false"));
+        let test = job.push_named("test", Command::new("true"));
+        let package = job.push_named("package", Command::new("true"));
+        job.push_named("lint", Command::new("true"));
+        job.depends_on(&test, &build).depends_on(&package, &test);
+
+        let report = job.run_and_report().unwrap();
+        assert!(matches!(report.tasks[0].outcome, TaskOutcome::Failed(_)));
+        assert_eq!(report.tasks[1].outcome, TaskOutcome::Skipped);
+        assert_eq!(report.tasks[2].outcome, TaskOutcome::Skipped);
+        assert_eq!(report.tasks[3].outcome, TaskOutcome::Succeeded);
+    }
+
+    #[test]
+    fn invalid_dependencies() {
+        let mut job = Job::new();
+        let first = job.push_named("first", Command::new("true"));
+        job.depends_on(&first, "missing");
+        let error = job.validate().unwrap_err();
+        assert!(matches!(
+            error,
+            RunningError::Job(JobError::DependencyMissing { .. })
+        ));
+
+        let mut job = Job::new();
+        let first = job.push_named("first", Command::new("true"));
+        let second = job.push_named("second", Command::new("true"));
+        job.depends_on(&first, &second).depends_on(&second, &first);
+        let error = job.run_and_report().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Tasks depend on each other in a cycle: first -> second -> first"
+        );
+
+        let mut job = Job::new();
+        job.push_named("twin", Command::new("true"));
+        job.push_named("twin", Command::new("true"));
+        let third = job.push_named("third", Command::new("true"));
+        job.depends_on("twin", &third);
+        let error = job.validate().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!("Task `twin` names more than one task in job {}", job.id)
+        );
+        assert_eq!(error.code(), ErrorCode::JobTaskAmbiguous);
+
+        let mut job = Job::new();
+        let first = job.push_named("first", Command::new("true"));
+        job.push_named("twin", Command::new("true"));
+        job.push_named("twin", Command::new("true"));
+        job.depends_on(&first, "twin");
+        let error = job.validate().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Task `first` depends on `twin`, which names more than one task"
+        );
+    }
+
+    #[test]
+    fn task_handles() {
+        let mut job = Job::new().error_policy(ErrorPolicy::ContinueAndCollect);
+        let sum = job.push(Callable::new(|| 2 + 2));
+        let greeting = job.push(Callable::new(|| String::from("Hello")));
+        let echo = job.push(Command::new("echo").arg("World"));
+        let failing = job.push(Command::new("false"));
+        assert!(!sum.is_finished());
+
+        job.run_and_return().unwrap();
+        assert_eq!(sum.take().unwrap().unwrap(), 4);
+        assert_eq!(greeting.take().unwrap().unwrap(), "Hello");
+        assert_eq!(echo.take().unwrap().unwrap().stdout, b"World\n");
+        assert!(failing.take().unwrap().is_err());
+        assert!(sum.take().is_none());
+    }
+
+    #[test]
+    fn fed_tasks() {
+        let mut job = Job::new().max_concurrency(2);
+        let base = job.push(Callable::new(|| 6));
+        let factor = job.push(Callable::new(|| 7));
+        let product = job.push_fed(
+            "product",
+            Callable::new(|base: i32, factor: i32| base * factor),
+            (base.clone(), factor),
+        );
+        let echo = job.push_fed_with(
+            "echo",
+            Command::new("echo").arg("Product:"),
+            product.clone(),
+            |product| CommandInput::new().arg(product.to_string()),
+        );
+        let count = job.push_fed_with("count", Command::new("wc").arg("-w"), echo, |output| {
+            CommandInput::new().stdin(output.stdout)
+        });
+
+        job.run_and_return().unwrap();
+        assert_eq!(base.take().unwrap().unwrap(), 6);
+        assert_eq!(product.take().unwrap().unwrap(), 42);
+        let count = String::from_utf8(count.take().unwrap().unwrap().stdout).unwrap();
+        assert_eq!(count.trim(), "2");
+    }
+
+    #[test]
+    fn observers() {
+        #[derive(Default)]
+        struct Recorder {
+            events: Mutex<Vec<String>>,
+        }
+
+        impl JobObserver for Recorder {
+            fn job_finished(&self, _event: &JobEvent, report: &JobReport) {
+                let message = format!("finished {}", report.succeeded_count());
+                self.events.lock().unwrap().push(message);
+            }
+
+            fn task_started(&self, event: &TaskEvent) {
+                self.events
+                    .lock()
+                    .unwrap()
+                    .push(format!("started {}", event.name));
+            }
+
+            fn task_failed(&self, event: &TaskEvent, _message: &str) {
+                self.events
+                    .lock()
+                    .unwrap()
+                    .push(format!("failed {}", event.name));
+            }
+
+            fn task_skipped(&self, event: &TaskEvent) {
+                self.events
+                    .lock()
+                    .unwrap()
+                    .push(format!("skipped {}", event.name));
+            }
+
+            fn output_line(&self, event: &TaskEvent, stream: OutputStream, line: &str) {
+                let message = format!("{} {:?} {}", event.name, stream, line);
+                self.events.lock().unwrap().push(message);
+            }
+        }
+
+        let recorder = Arc::new(Recorder::default());
+        let mut job = Job::new().observer(recorder.clone()).observer(LogObserver);
+        job.push_named("echo", Command::new("printf").arg("one\\ntwo\\n"));
+        let failing = job.push_named("failing", Command::new("false"));
+        let skipped = job.push_named("skipped", Command::new("true"));
+        job.depends_on(&skipped, &failing);
+
+        assert!(job.run_and_return().is_err());
+        assert_eq!(
+            *recorder.events.lock().unwrap(),
+            vec![
+                "started echo",
+                "echo Stdout one",
+                "echo Stdout two",
+                "started failing",
+                "failed failing",
+                "skipped skipped",
+                "finished 1",
+            ]
+        );
+    }
+
+    #[test]
+    fn cancellation() {
+        let token = CancellationToken::new();
+        let mut job = Job::new()
+            .max_concurrency(2)
+            .cancellation_token(token.clone());
+        let sleeping = job.push_named("sleeping", Command::new("sleep").arg("10"));
+        let polling = job.push_cancellable(
+            "polling",
+            Callable::new(|token: CancellationToken| {
+                while !token.is_cancelled() {
+                    thread::sleep(Duration::from_millis(5));
+                }
+                "stopped"
+            }),
+        );
+        let after = job.push_named("after", Command::new("true"));
+        job.depends_on(&after, &sleeping);
+
+        let canceller = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            token.cancel();
+        });
+        let start = Instant::now();
+        let error = job.run_and_return().unwrap_err();
+        canceller.join().unwrap();
+        assert!(start.elapsed() < Duration::from_secs(5));
+        let report = match &error {
+            RunningError::Job(JobError::JobCancelled { report, .. }) => report,
+            _ => panic!("Expected the job to be cancelled, but got: {}", error),
+        };
+        let outcomes: Vec<_> = report
+            .tasks
+            .iter()
+            .map(|task| task.outcome.clone())
+            .collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                TaskOutcome::Cancelled,
+                TaskOutcome::Succeeded,
+                TaskOutcome::Cancelled
+            ]
+        );
+        assert!(report.cancelled);
+        assert_eq!(polling.take().unwrap().unwrap(), "stopped");
+    }
+
+    #[test]
+    fn finally_tasks() {
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let mut job = failing_job(ErrorPolicy::StopAtFirstFailure, &order);
+        let cleanup_order = order.clone();
+        let cleanup = job.push_finally(
+            "cleanup",
+            Callable::new(move || cleanup_order.lock().unwrap().push(9)),
+        );
+
+        let report = job.run_and_report().unwrap();
+        assert_eq!(*order.lock().unwrap(), vec![0, 1, 9]);
+        assert_eq!(report.tasks.len(), 5);
+        assert_eq!(report.tasks[4].id, cleanup.id());
+        assert_eq!(report.tasks[4].outcome, TaskOutcome::Succeeded);
+
+        // A cleanup task that panics does not stop the ones after it
+        let mut job = Job::new();
+        job.push_named("work", Callable::new(|| ()));
+        job.push_finally("panicking", Panicking);
+        job.push_finally("cleanup", Command::new("true"));
+        let report = job.run_and_report().unwrap();
+        let outcomes: Vec<_> = report
+            .tasks
+            .iter()
+            .map(|task| task.outcome.clone())
+            .collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                TaskOutcome::Succeeded,
+                TaskOutcome::Failed(String::from("Task panicked: Panicking test...")),
+                TaskOutcome::Succeeded
+            ]
+        );
+    }
+
+    #[test]
+    fn task_attribute() {
+        assert_eq!(double::task(21).run_and_return().unwrap(), 42);
+        let (output, line) = greet::task(String::from("Ann"), 2).run_and_log().unwrap();
+        assert_eq!(output, "AnnAnn");
+        assert_eq!(line, "greet(name = Ann, times = 2) -> AnnAnn");
+        let rectangle = Rectangle {
+            width: 2,
+            height: 3,
+        };
+        let (_, line) = area::task(rectangle).run_and_log().unwrap();
+        assert_eq!(
+            line,
+            "area(rectangle = Rectangle { width: 2, height: 3 }) -> 6"
+        );
+
+        let mut job = Job::new()
+            .error_policy(ErrorPolicy::ContinueAndCollect)
+            .parallel();
+        let doubled = double::push(&mut job, 4);
+        let start = job.push_named("start", Callable::new(|| ()));
+        let slept = sleep::push(&mut job);
+        let started = Instant::now();
+        let report = job.run_and_report().unwrap();
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(doubled.take().unwrap().unwrap(), 8);
+        assert!(start.is_finished());
+
+        let tagged: Vec<_> = report.tagged("math").collect();
+        assert_eq!(tagged.len(), 2);
+        assert_eq!(tagged[0].name, "doubling");
+        assert_eq!(tagged[0].outcome, TaskOutcome::Succeeded);
+        assert_eq!(tagged[1].name, "sleep");
+        assert_eq!(
+            tagged[1].outcome,
+            TaskOutcome::Failed(String::from("Task timed out after 50ms"))
+        );
+        let error = slept.take().unwrap().unwrap_err();
+        assert_eq!(error.code(), ErrorCode::JobTaskTimedOut);
+        assert!(error.is_cancellation());
+    }
+
+    #[test]
+    fn logging_format() {
+        let log_inner = |handle: &'static str| {
+            return Callable::new(move || {
+                let (_, line) = LoggedCallable::new(|| 1, handle).run_and_log().unwrap();
+                line
+            });
+        };
+        let mut outer =
+            Job::new().logging_format("job: {handle}".parse::<LoggingFormat>().unwrap());
+        let outer_line = outer.push(log_inner("outer"));
+        let inner_line = outer.push(Callable::new(move || {
+            let mut inner = Job::new();
+            let inner_line = inner.push(log_inner("inner"));
+            inner.run().unwrap();
+            inner_line.take().unwrap().unwrap()
+        }));
+        outer.run().unwrap();
+        assert_eq!(outer_line.take().unwrap().unwrap(), "job: outer");
+        assert_eq!(inner_line.take().unwrap().unwrap(), "job: inner");
+    }
+
+    #[test]
+    fn derived_task() {
+        let count = Count {
+            limit: 2,
+            label: "twice",
+            attempts: 0,
+        };
+        assert_eq!(count.task_name(), "counting");
+        assert_eq!(count.task_arguments(), "limit = 2, label = twice");
+
+        let mut job = Job::new().error_policy(ErrorPolicy::ContinueAndCollect);
+        let retried = job.push_described(Retry::new(count).max_attempts(2));
+        let failed = job.push_described(Count {
+            limit: 3,
+            label: "thrice",
+            attempts: 0,
+        });
+        let report = job.run_and_report().unwrap();
+        assert_eq!(retried.take().unwrap().unwrap(), 2);
+        assert!(failed.take().unwrap().is_err());
+        assert_eq!(report.tasks[0].name, "counting");
+        assert_eq!(report.tasks[0].outcome, TaskOutcome::Succeeded);
+        assert!(matches!(report.tasks[1].outcome, TaskOutcome::Failed(_)));
+
+        let mut count = Count {
+            limit: 2,
+            label: "logged",
+            attempts: 0,
+        };
+        let logs = capture_logs(|| assert!(count.run_and_return().is_err()));
+        assert_eq!(logs[1].0, log::Level::Error);
+        assert!(logs[1].1.contains("attempt 1 of 2"));
+    }
+
+    #[cfg(feature = "signal_support")]
+    #[test]
+    fn signals() {
+        use signal_hook::consts::signal::SIGINT;
+
+        // A raised signal reaches the whole process, and would kill the other
+        // tests if it came while no job handles signals, so this test runs
+        // again alone in a process of its own
+        if std::env::var_os("RUNNING_SIGNALS_TEST").is_none() {
+            let output = std::process::Command::new(std::env::current_exe().unwrap())
+                .args(["runnable::tests::signals", "--exact", "--test-threads=1"])
+                .env("RUNNING_SIGNALS_TEST", "1")
+                .output()
+                .unwrap();
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            assert!(
+                output.status.success(),
+                "{}{}",
+                stdout,
+                String::from_utf8_lossy(&output.stderr)
+            );
+            assert!(stdout.contains("test result: ok. 1 passed"), "{}", stdout);
+            return;
+        }
+
+        let mut job = Job::new().handle_signals(Duration::from_millis(100));
+        // The shell ignores the forwarded signal, so it has to be killed
+        job.push_named(
+            "stubborn",
+            Command::new("sh").args(["-c", "trap '' INT; sleep 10"]),
+        );
+        job.push_named("never", Command::new("true"));
+        let cleanup = job.push_finally("cleanup", Command::new("true"));
+
+        let signaller = thread::spawn(|| {
+            thread::sleep(Duration::from_millis(50));
+            signal_hook::low_level::raise(SIGINT).unwrap();
+        });
+        let start = Instant::now();
+        let error = job.run_and_return().unwrap_err();
+        signaller.join().unwrap();
+        assert!(start.elapsed() < Duration::from_secs(5));
+        let report = match &error {
+            RunningError::Job(JobError::JobInterrupted { report, .. }) => report,
+            _ => panic!("Expected the job to be interrupted, but got: {}", error),
+        };
+        let outcomes: Vec<_> = report
+            .tasks
+            .iter()
+            .map(|task| task.outcome.clone())
+            .collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                TaskOutcome::Cancelled,
+                TaskOutcome::Cancelled,
+                TaskOutcome::Succeeded
+            ]
+        );
+        assert!(report.interrupted && !report.cancelled);
+        assert!(cleanup.take().unwrap().is_ok());
+    }
 }
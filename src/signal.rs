@@ -0,0 +1,320 @@
+// IMPORTS
+use crate::cancellation::CancellationToken;
+use crate::{Error, RunningError};
+use signal_hook::consts::signal::{SIGINT, SIGKILL, SIGTERM};
+use signal_hook::iterator::Signals;
+use snafu::{Backtrace, ResultExt, Snafu};
+use std::process;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::thread;
+use std::time::Duration;
+
+// ERRORS
+
+#[derive(Debug, Snafu)]
+pub enum SignalError {
+    #[snafu(display("Signal handler could not be installed: {}", source))]
+    SignalHandlerFailed {
+        source: std::io::Error,
+        backtrace: Backtrace,
+    },
+}
+
+impl From<SignalError> for Error {
+    fn from(signal_error: SignalError) -> Self {
+        RunningError::Signal(signal_error)
+    }
+}
+
+// STRUCT DECLARATIONS
+
+/// The interruption state of a job that handles signals. The first SIGINT or
+/// SIGTERM stops the job from starting more tasks and is forwarded to the
+/// process groups of its running commands. Once the grace period is over, the
+/// cancellation token is cancelled, which kills the remaining commands
+pub(crate) struct Interruption {
+    signal: AtomicI32, // the signal that interrupted the job, or 0
+    grace_period: Duration,
+    cancellation_token: CancellationToken,
+    process_groups: Mutex<Vec<u32>>, // led by the running commands of the job
+}
+
+/// Keeps the process group of a running command known to the interruption of
+/// its job, so that a second signal can kill it, until dropped
+pub(crate) struct TrackedProcessGroup {
+    interruption: Arc<Interruption>,
+    process_group: u32,
+}
+
+/// What the signal listener does about a signal, once the jobs are
+/// interrupted or their commands killed
+#[derive(Debug, PartialEq, Eq)]
+enum SignalResponse {
+    /// No job handles signals, so the signal does what it does by default
+    Default,
+    /// The jobs were interrupted, and stop within their grace periods
+    Interrupted,
+    /// A second signal killed the running commands, and the process exits with
+    /// the given code
+    Exit(i32),
+}
+
+/// Whether the signal listener thread was started, and the interruptions of
+/// the jobs that are running
+struct Listener {
+    started: bool,
+    interruptions: Vec<Weak<Interruption>>,
+}
+
+// GLOBAL VARIABLES
+
+static LISTENER: Mutex<Listener> = Mutex::new(Listener {
+    started: false,
+    interruptions: Vec::new(),
+});
+
+// STRUCT IMPLEMENTATIONS
+
+impl Interruption {
+    /// Registers a running job for signals, starting the signal listener if
+    /// it is not running yet. The job stays registered until the returned
+    /// interruption is dropped
+    pub(crate) fn register(
+        grace_period: Duration,
+        cancellation_token: CancellationToken,
+    ) -> Result<Arc<Interruption>, Error> {
+        let mut listener = LISTENER.lock().unwrap();
+        if !listener.started {
+            let mut signals = Signals::new([SIGINT, SIGTERM]).context(SignalHandlerFailed)?;
+            thread::spawn(move || {
+                for signal in signals.forever() {
+                    on_signal(signal);
+                }
+            });
+            listener.started = true;
+        }
+        let interruption = Arc::new(Interruption {
+            signal: AtomicI32::new(0),
+            grace_period,
+            cancellation_token,
+            process_groups: Mutex::new(Vec::new()),
+        });
+        listener.interruptions.push(Arc::downgrade(&interruption));
+        return Ok(interruption);
+    }
+
+    /// The signal that interrupted the job, if any
+    pub(crate) fn signal(&self) -> Option<i32> {
+        return match self.signal.load(Ordering::SeqCst) {
+            0 => None,
+            signal => Some(signal),
+        };
+    }
+
+    /// Tracks the process group led by a running command of the job
+    pub(crate) fn track(self: &Arc<Self>, process_group: u32) -> TrackedProcessGroup {
+        self.process_groups.lock().unwrap().push(process_group);
+        return TrackedProcessGroup {
+            interruption: self.clone(),
+            process_group,
+        };
+    }
+
+    /// Kills the process groups of the running commands of the job
+    fn kill_process_groups(&self) {
+        for &process_group in self.process_groups.lock().unwrap().iter() {
+            kill_process_group(process_group);
+        }
+    }
+
+    /// Marks the job as interrupted, and kills what is left of it once the
+    /// grace period is over
+    fn interrupt(&self, signal: i32) {
+        self.signal.store(signal, Ordering::SeqCst);
+        let (grace_period, cancellation_token) =
+            (self.grace_period, self.cancellation_token.clone());
+        thread::spawn(move || {
+            thread::sleep(grace_period);
+            cancellation_token.cancel();
+        });
+    }
+}
+
+// FUNCTIONS
+
+/// Interrupts every running job that handles signals. A second signal kills
+/// the process groups of their running commands, so that none are left
+/// behind, and exits the process right away. A signal that arrives while no
+/// such job is running does what it would have done without a handler
+fn on_signal(signal: i32) {
+    let mut listener = LISTENER.lock().unwrap();
+    listener
+        .interruptions
+        .retain(|interruption| interruption.strong_count() > 0);
+    let interruptions: Vec<_> = listener
+        .interruptions
+        .iter()
+        .filter_map(|interruption| interruption.upgrade())
+        .collect();
+    match respond(signal, &interruptions) {
+        SignalResponse::Default => {
+            let _ = signal_hook::low_level::emulate_default_handler(signal);
+        }
+        SignalResponse::Interrupted => {}
+        SignalResponse::Exit(code) => process::exit(code),
+    }
+}
+
+/// Interrupts the given jobs, or kills their running commands if they were
+/// interrupted already, and tells what is left to do about the signal
+fn respond(signal: i32, interruptions: &[Arc<Interruption>]) -> SignalResponse {
+    if interruptions.is_empty() {
+        return SignalResponse::Default;
+    }
+    if interruptions
+        .iter()
+        .any(|interruption| interruption.signal().is_some())
+    {
+        log::warn!(
+            "Received a second signal ({}). Killing the running commands and exiting",
+            signal
+        );
+        for interruption in interruptions {
+            interruption.kill_process_groups();
+        }
+        return SignalResponse::Exit(128 + signal);
+    }
+    for interruption in interruptions {
+        log::warn!(
+            "Received signal {}. Stopping the job, and killing its commands in {:?}",
+            signal,
+            interruption.grace_period
+        );
+        interruption.interrupt(signal);
+    }
+    return SignalResponse::Interrupted;
+}
+
+/// Sends a signal to the process group led by a child process. A failure,
+/// such as when the group has already exited, is only logged
+pub(crate) fn signal_process_group(child_id: u32, signal: i32) {
+    // SAFETY: killpg only takes integers and touches no memory of this
+    // process. The child was started in a process group of its own, whose ID
+    // is the ID of the child, so no other group is signalled
+    let result = unsafe { libc::killpg(child_id as libc::pid_t, signal) };
+    if result == -1 {
+        log::debug!(
+            "Signal {} could not be sent to process group {}: {}",
+            signal,
+            child_id,
+            std::io::Error::last_os_error()
+        );
+    }
+}
+
+/// Kills the process group led by a child process, including the processes
+/// that the child started
+pub(crate) fn kill_process_group(child_id: u32) {
+    signal_process_group(child_id, SIGKILL);
+}
+
+// TRAIT IMPLEMENTATIONS
+
+impl Drop for TrackedProcessGroup {
+    fn drop(&mut self) {
+        let mut process_groups = self.interruption.process_groups.lock().unwrap();
+        if let Some(index) = process_groups
+            .iter()
+            .position(|&group| group == self.process_group)
+        {
+            process_groups.swap_remove(index);
+        }
+    }
+}
+
+// TESTS
+
+#[cfg(test)]
+mod tests {
+
+    // IMPORTS
+
+    use super::{respond, Interruption, SignalResponse, LISTENER};
+    use crate::cancellation::CancellationToken;
+    use signal_hook::consts::signal::{SIGINT, SIGTERM};
+    use std::os::unix::process::{CommandExt, ExitStatusExt};
+    use std::process::Command;
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    // FUNCTIONS
+
+    /// Whether the listener still holds the given interruption
+    fn is_registered(interruption: &Arc<Interruption>) -> bool {
+        return LISTENER
+            .lock()
+            .unwrap()
+            .interruptions
+            .iter()
+            .any(|registered| registered.as_ptr() == Arc::as_ptr(interruption));
+    }
+
+    // TESTS
+
+    #[test]
+    fn register_and_track() {
+        let interruption =
+            Interruption::register(Duration::from_secs(1), CancellationToken::new()).unwrap();
+        assert!(is_registered(&interruption));
+        assert_eq!(interruption.signal(), None);
+
+        let first = interruption.track(1001);
+        let second = interruption.track(1002);
+        assert_eq!(
+            *interruption.process_groups.lock().unwrap(),
+            vec![1001, 1002]
+        );
+        drop(first);
+        assert_eq!(*interruption.process_groups.lock().unwrap(), vec![1002]);
+        drop(second);
+        assert!(interruption.process_groups.lock().unwrap().is_empty());
+
+        let weak = Arc::downgrade(&interruption);
+        drop(interruption);
+        assert!(weak.upgrade().is_none());
+    }
+
+    #[test]
+    fn first_and_second_signals() {
+        assert_eq!(respond(SIGINT, &[]), SignalResponse::Default);
+
+        let token = CancellationToken::new();
+        let interruption =
+            Interruption::register(Duration::from_millis(50), token.clone()).unwrap();
+        let mut child = Command::new("sleep")
+            .arg("10")
+            .process_group(0)
+            .spawn()
+            .unwrap();
+        let _tracked = interruption.track(child.id());
+
+        let interruptions = [interruption.clone()];
+        assert_eq!(
+            respond(SIGTERM, &interruptions),
+            SignalResponse::Interrupted
+        );
+        assert_eq!(interruption.signal(), Some(SIGTERM));
+        assert!(!token.is_cancelled());
+        thread::sleep(Duration::from_millis(200));
+        assert!(token.is_cancelled());
+
+        assert_eq!(
+            respond(SIGINT, &interruptions),
+            SignalResponse::Exit(128 + SIGINT)
+        );
+        let status = child.wait().unwrap();
+        assert_eq!(status.signal(), Some(libc::SIGKILL));
+    }
+}
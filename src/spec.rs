@@ -0,0 +1,375 @@
+// IMPORTS
+use crate::callable::LoggingFormat;
+use crate::instruction::{Command, CommandFormat};
+use crate::retry::Retry;
+use crate::runnable::{Dependency, ErrorPolicy, Job};
+use crate::Error;
+use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+// STRUCT DECLARATIONS
+
+/// The definition of a [Job] of commands, which unlike a job can be
+/// serialized, persisted, and sent to other processes. It is serialized as
+/// `{"error_policy": "...", "max_concurrency": 4, "logging_format": "...",
+/// "tasks": [...]}`, where every field may be left out. Turn it into a job with
+/// [JobSpec::into_job]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JobSpec {
+    #[serde(default)]
+    pub error_policy: ErrorPolicy,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_concurrency: Option<usize>, // runs the tasks one by one if left out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logging_format: Option<LoggingFormat>,
+    #[serde(default)]
+    pub tasks: Vec<TaskSpec>,
+}
+
+/// The definition of a task of a [JobSpec]: a named command, with how long it
+/// may run, how many times it is retried, the names of the tasks it depends
+/// on, and its tags. It is serialized flat, with the fields of the command
+/// beside those of the task, as
+/// `{"name": "...", "program": "...", "args": [...], "timeout": 1.5, "retries":
+/// 2, "depends_on": [...], "tags": [...]}`, where the timeout is in seconds.
+/// Every field but the name and the program may be left out. Instead of a
+/// program and its arguments, a task may be deserialized from a `"script"`,
+/// which is run by `sh -c`
+#[derive(Debug, Clone)]
+pub struct TaskSpec {
+    pub name: String,
+    pub command: Command,
+    pub timeout: Option<Duration>,
+    pub retries: usize, // attempts after the first one
+    pub depends_on: Vec<String>,
+    pub tags: Vec<String>,
+}
+
+/// The serialized form of a [TaskSpec], which repeats the fields of the
+/// serialized form of a [Command]
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TaskFormat {
+    name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    program: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    script: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    args: Vec<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    env: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cwd: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    stdin: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    allowed_exit_codes: Vec<i32>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "optional_seconds"
+    )]
+    timeout: Option<Duration>,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    retries: usize,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    depends_on: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+}
+
+// STRUCT IMPLEMENTATIONS
+
+impl JobSpec {
+    /// Creates an empty job definition
+    pub fn new() -> Self {
+        return JobSpec::default();
+    }
+
+    /// Appends the definition of a task
+    pub fn task(mut self, task: TaskSpec) -> Self {
+        self.tasks.push(task);
+        return self;
+    }
+
+    /// Builds the job that this defines, and validates its dependencies. Tasks
+    /// with retries are wrapped in a [Retry], which retries them right away
+    pub fn into_job(self) -> Result<Job, Error> {
+        let mut job = Job::new().error_policy(self.error_policy);
+        if let Some(max_concurrency) = self.max_concurrency {
+            job = job.max_concurrency(max_concurrency);
+        }
+        if let Some(logging_format) = self.logging_format {
+            job = job.logging_format(logging_format);
+        }
+        for task in self.tasks {
+            let task_handle = match task.retries {
+                0 => job.push_named(task.name, task.command),
+                retries => {
+                    job.push_named(
+                        task.name,
+                        Retry::new(task.command).max_attempts(retries + 1),
+                    )
+                }
+            };
+            if let Some(timeout) = task.timeout {
+                job.timeout(&task_handle, timeout);
+            }
+            for tag in task.tags {
+                job.tag(&task_handle, tag);
+            }
+            for dependency in task.depends_on {
+                job.depends_on(&task_handle, Dependency::Name(dependency));
+            }
+        }
+        job.validate()?;
+        return Ok(job);
+    }
+}
+
+impl TaskSpec {
+    /// Creates the definition of a task that runs a command once, with no
+    /// timeout, dependencies or tags
+    pub fn new<S: Into<String>>(name: S, command: Command) -> Self {
+        return TaskSpec {
+            name: name.into(),
+            command,
+            timeout: None,
+            retries: 0,
+            depends_on: Vec::new(),
+            tags: Vec::new(),
+        };
+    }
+
+    /// Fails the task if it is still running after the given duration
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        return self;
+    }
+
+    /// Sets how many times the task is retried after its first attempt fails
+    pub fn retries(mut self, retries: usize) -> Self {
+        self.retries = retries;
+        return self;
+    }
+
+    /// Makes the task wait for the task with the given name, and only run if
+    /// it succeeds
+    pub fn depends_on<S: Into<String>>(mut self, dependency: S) -> Self {
+        self.depends_on.push(dependency.into());
+        return self;
+    }
+
+    /// Tags the task
+    pub fn tag<S: Into<String>>(mut self, tag: S) -> Self {
+        self.tags.push(tag.into());
+        return self;
+    }
+}
+
+// FUNCTIONS
+
+fn is_zero(value: &usize) -> bool {
+    return *value == 0;
+}
+
+/// Serializes durations as seconds, with a fractional part
+pub(crate) mod seconds {
+    use serde::{de, Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub(crate) fn serialize<S: Serializer>(
+        duration: &Duration,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        return serializer.serialize_f64(duration.as_secs_f64());
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Duration, D::Error> {
+        let seconds = f64::deserialize(deserializer)?;
+        return Duration::try_from_secs_f64(seconds).map_err(|_error| {
+            de::Error::custom(format!("{} is not a valid number of seconds", seconds))
+        });
+    }
+}
+
+/// Serializes optional durations as seconds, with a fractional part
+pub(crate) mod optional_seconds {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub(crate) fn serialize<S: Serializer>(
+        duration: &Option<Duration>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        return match duration {
+            Some(duration) => super::seconds::serialize(duration, serializer),
+            None => serializer.serialize_none(),
+        };
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Duration>, D::Error> {
+        #[derive(Deserialize)]
+        struct Seconds(#[serde(with = "super::seconds")] Duration);
+
+        return Ok(Option::<Seconds>::deserialize(deserializer)?.map(|Seconds(duration)| duration));
+    }
+}
+
+// TRAIT IMPLEMENTATIONS
+
+impl Serialize for TaskSpec {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let command = CommandFormat::from_command(&self.command).map_err(ser::Error::custom)?;
+        return TaskFormat {
+            name: self.name.clone(),
+            program: Some(command.program),
+            script: None,
+            args: command.args,
+            env: command.env,
+            cwd: command.cwd,
+            stdin: command.stdin,
+            allowed_exit_codes: command.allowed_exit_codes,
+            timeout: self.timeout,
+            retries: self.retries,
+            depends_on: self.depends_on.clone(),
+            tags: self.tags.clone(),
+        }
+        .serialize(serializer);
+    }
+}
+
+impl<'de> Deserialize<'de> for TaskSpec {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let format = TaskFormat::deserialize(deserializer)?;
+        let name = &format.name;
+        let invalid = |message: &str| de::Error::custom(format!("task `{}` {}", name, message));
+        let (program, args) = match (format.program, format.script) {
+            (Some(program), None) if program.is_empty() => {
+                return Err(invalid("has an empty program"))
+            }
+            (Some(program), None) => (program, format.args),
+            (None, Some(script)) if format.args.is_empty() => {
+                ("sh".to_string(), vec!["-c".to_string(), script])
+            }
+            (None, Some(_script)) => return Err(invalid("has a script, which cannot take args")),
+            (Some(_program), Some(_script)) => {
+                return Err(invalid("has both a program and a script"))
+            }
+            (None, None) => return Err(invalid("has neither a program nor a script")),
+        };
+        let command = CommandFormat {
+            program,
+            args,
+            env: format.env,
+            cwd: format.cwd,
+            stdin: format.stdin,
+            allowed_exit_codes: format.allowed_exit_codes,
+        }
+        .into_command();
+        return Ok(TaskSpec {
+            name: format.name,
+            command,
+            timeout: format.timeout,
+            retries: format.retries,
+            depends_on: format.depends_on,
+            tags: format.tags,
+        });
+    }
+}
+
+// TESTS
+
+#[cfg(test)]
+mod tests {
+
+    // IMPORTS
+
+    use super::{JobSpec, TaskSpec};
+    use crate::instruction::Command;
+    use crate::runnable::{ErrorPolicy, JobError, JobReport, TaskOutcome};
+    use crate::{RunAndReturn, RunningError};
+    use std::time::Duration;
+
+    // TESTS
+
+    #[test]
+    fn round_trip() {
+        let spec = JobSpec::new()
+            .task(TaskSpec::new("build", Command::new("true")).tag("ci"))
+            .task(
+                TaskSpec::new("test", Command::new("sh").args(["-c", "exit 0"]))
+                    .timeout(Duration::from_millis(1500))
+                    .retries(2)
+                    .depends_on("build"),
+            );
+        let json = serde_json::to_string(&spec).unwrap();
+        assert_eq!(
+            json,
+            r#"{"error_policy":"stop_at_first_failure","tasks":[{"name":"build","program":"true","tags":["ci"]},{"name":"test","program":"sh","args":["-c","exit 0"],"timeout":1.5,"retries":2,"depends_on":["build"]}]}"#
+        );
+
+        let spec: JobSpec = serde_json::from_str(&json).unwrap();
+        assert_eq!(spec.tasks[1].timeout, Some(Duration::from_millis(1500)));
+        let report = spec.into_job().unwrap().run_and_return().unwrap();
+        let names: Vec<_> = report.tasks.iter().map(|task| task.name.as_str()).collect();
+        assert_eq!(names, ["build", "test"]);
+        assert_eq!(report.tagged("ci").count(), 1);
+
+        let report_json = serde_json::to_value(&report).unwrap();
+        assert_eq!(report_json["tasks"][0]["outcome"], "succeeded");
+        assert_eq!(report_json["tasks"][0]["tags"][0], "ci");
+    }
+
+    #[test]
+    fn failures() {
+        let spec: JobSpec = serde_json::from_str(
+            r#"{
+                "error_policy": "continue_and_collect",
+                "logging_format": "{handle} -> {status}",
+                "tasks": [
+                    {"name": "failing", "program": "false"},
+                    {"name": "skipped", "program": "true", "depends_on": ["failing"]}
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(spec.error_policy, ErrorPolicy::ContinueAndCollect);
+        assert_eq!(
+            serde_json::to_value(&spec.logging_format).unwrap(),
+            "{handle} -> {status}"
+        );
+        let report = spec.into_job().unwrap().run_and_return().unwrap();
+        assert!(matches!(report.tasks[0].outcome, TaskOutcome::Failed(_)));
+        assert_eq!(report.tasks[1].outcome, TaskOutcome::Skipped);
+
+        let report_json = serde_json::to_value(&report).unwrap();
+        assert!(report_json["tasks"][0]["outcome"]["failed"].is_string());
+        assert_eq!(report_json["tasks"][1]["outcome"], "skipped");
+        let report: JobReport = serde_json::from_value(report_json).unwrap();
+        assert_eq!(report.tasks[1].outcome, TaskOutcome::Skipped);
+
+        let spec = JobSpec::new()
+            .task(TaskSpec::new("orphan", Command::new("true")).depends_on("missing"));
+        assert!(matches!(
+            spec.into_job(),
+            Err(RunningError::Job(JobError::DependencyMissing { .. }))
+        ));
+        assert!(serde_json::from_str::<JobSpec>(
+            r#"{"tasks":[{"name":"typo","program":"true","retry":1}]}"#
+        )
+        .is_err());
+        assert!(serde_json::from_str::<JobSpec>(
+            r#"{"tasks":[{"name":"negative","program":"true","timeout":-1}]}"#
+        )
+        .is_err());
+    }
+}
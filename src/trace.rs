@@ -0,0 +1,316 @@
+// IMPORTS
+use crate::observer::OutputStream;
+use crate::runnable::TaskContext;
+use crate::Error;
+use std::fmt::Display;
+use std::time::Instant;
+use tracing::{field, Span};
+
+// STRUCT DECLARATIONS
+
+/// A run of a job, task, callable or command, traced in a span from when it
+/// starts until its completion event
+pub(crate) struct TracedRun {
+    span: Span,
+    start: Instant,
+}
+
+// STRUCT IMPLEMENTATIONS
+
+impl TracedRun {
+    /// Starts tracing a run in the given span
+    pub(crate) fn start(span: Span) -> Self {
+        return TracedRun {
+            span,
+            start: Instant::now(),
+        };
+    }
+
+    /// The span of the run
+    pub(crate) fn span(&self) -> &Span {
+        return &self.span;
+    }
+
+    /// Emits the completion event of the run inside its span, at the error
+    /// level if the run failed
+    pub(crate) fn complete(&self, outcome: &str, error: Option<&dyn Display>) {
+        let duration_ms = self.start.elapsed().as_secs_f64() * 1000.0;
+        match error {
+            Some(error) => {
+                tracing::error!(parent: &self.span, outcome, duration_ms, error = %error, "run completed")
+            }
+            None => tracing::info!(parent: &self.span, outcome, duration_ms, "run completed"),
+        }
+    }
+
+    /// Emits the completion event of a run that returned the given result
+    pub(crate) fn complete_result<T>(&self, result: &Result<T, Error>) {
+        match result {
+            Ok(_) => self.complete("succeeded", None),
+            Err(error) => self.complete("failed", Some(error)),
+        }
+    }
+}
+
+// FUNCTIONS
+
+/// The span of a job run. When jobs are nested, it is opened inside the span
+/// of the task that runs the job
+pub(crate) fn job_span(job_id: usize, parent_job_id: Option<usize>) -> Span {
+    let span = tracing::info_span!("job", job_id, parent_job_id = field::Empty);
+    if let Some(parent_job_id) = parent_job_id {
+        span.record("parent_job_id", parent_job_id);
+    }
+    return span;
+}
+
+/// The span of a task, inside the span of its job, even when the task runs on
+/// another thread than the job
+pub(crate) fn task_span(context: &TaskContext, task_id: usize, task_name: &str) -> Span {
+    return tracing::info_span!(parent: context.span(), "task", job_id = context.job_id(), task_id, task_name);
+}
+
+/// The span of a run of a callable. A logged callable gives its task ID and
+/// the strings of its handle and arguments, and a plain callable only the
+/// type of its handle
+pub(crate) fn callable_span(task_id: Option<usize>, handle: &str, arguments: Option<&str>) -> Span {
+    let span = tracing::info_span!(
+        "callable",
+        task_id = field::Empty,
+        handle,
+        arguments = field::Empty,
+        job_id = field::Empty
+    );
+    if let Some(task_id) = task_id {
+        span.record("task_id", task_id);
+    }
+    if let Some(arguments) = arguments {
+        span.record("arguments", arguments);
+    }
+    record_job(&span);
+    return span;
+}
+
+/// The span of a run of a command
+pub(crate) fn command_span(program: &str, arguments: &str) -> Span {
+    let span = tracing::info_span!("command", program, arguments, job_id = field::Empty);
+    record_job(&span);
+    return span;
+}
+
+/// Records the job of the task running on the current thread in a span
+fn record_job(span: &Span) {
+    if let Some(context) = TaskContext::current() {
+        span.record("job_id", context.job_id());
+    }
+}
+
+/// Emits an event for a line written by a command, inside the span of its run
+pub(crate) fn output_line(span: &Span, stream: OutputStream, line: &str) {
+    tracing::info!(parent: span, stream = ?stream, line, "output line");
+}
+
+// TESTS
+
+#[cfg(test)]
+mod tests {
+
+    // IMPORTS
+
+    use crate::callable::Callable;
+    use crate::instruction::Command;
+    use crate::runnable::Job;
+    use crate::RunAndReturn;
+    use std::cell::RefCell;
+    use std::fmt::Debug;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::{Arc, Mutex};
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    // STRUCT DECLARATIONS
+
+    /// A span or an event, with its parent span and its fields
+    #[derive(Debug, Clone)]
+    struct Recorded {
+        id: u64,
+        name: String,
+        parent: Option<u64>,
+        fields: Vec<(String, String)>,
+    }
+
+    /// Records every span and event, on any thread. Clones share the records
+    #[derive(Clone, Default)]
+    struct Recorder {
+        next_id: Arc<AtomicU64>,
+        spans: Arc<Mutex<Vec<Recorded>>>,
+        events: Arc<Mutex<Vec<Recorded>>>,
+    }
+
+    thread_local! {
+        static ENTERED: RefCell<Vec<u64>> = const { RefCell::new(Vec::new()) };
+    }
+
+    // STRUCT IMPLEMENTATIONS
+
+    impl Recorded {
+        fn field(&self, name: &str) -> Option<&str> {
+            return self
+                .fields
+                .iter()
+                .find(|(field, _)| field == name)
+                .map(|(_, value)| value.as_str());
+        }
+    }
+
+    impl Visit for Recorded {
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            self.fields
+                .push((field.name().to_string(), format!("{:?}", value)));
+        }
+
+        fn record_str(&mut self, field: &Field, value: &str) {
+            self.fields
+                .push((field.name().to_string(), value.to_string()));
+        }
+    }
+
+    /// The span that the next span or event is opened in, if it has no
+    /// explicit parent
+    fn current_span() -> Option<u64> {
+        return ENTERED.with(|entered| entered.borrow().last().copied());
+    }
+
+    impl Subscriber for Recorder {
+        fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+            return true;
+        }
+
+        fn new_span(&self, attributes: &Attributes<'_>) -> Id {
+            let id = self.next_id.fetch_add(1, Ordering::SeqCst) + 1;
+            let parent = match attributes.parent() {
+                Some(parent) => Some(parent.into_u64()),
+                None if attributes.is_contextual() => current_span(),
+                None => None,
+            };
+            let mut span = Recorded {
+                id,
+                name: attributes.metadata().name().to_string(),
+                parent,
+                fields: Vec::new(),
+            };
+            attributes.record(&mut span);
+            self.spans.lock().unwrap().push(span);
+            return Id::from_u64(id);
+        }
+
+        fn record(&self, span: &Id, values: &Record<'_>) {
+            let mut spans = self.spans.lock().unwrap();
+            if let Some(recorded) = spans
+                .iter_mut()
+                .find(|recorded| recorded.id == span.into_u64())
+            {
+                values.record(recorded);
+            }
+        }
+
+        fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            let parent = match event.parent() {
+                Some(parent) => Some(parent.into_u64()),
+                None if event.is_contextual() => current_span(),
+                None => None,
+            };
+            let mut recorded = Recorded {
+                id: 0,
+                name: event.metadata().name().to_string(),
+                parent,
+                fields: Vec::new(),
+            };
+            event.record(&mut recorded);
+            self.events.lock().unwrap().push(recorded);
+        }
+
+        fn enter(&self, span: &Id) {
+            ENTERED.with(|entered| entered.borrow_mut().push(span.into_u64()));
+        }
+
+        fn exit(&self, _span: &Id) {
+            ENTERED.with(|entered| entered.borrow_mut().pop());
+        }
+    }
+
+    // TESTS
+
+    #[test]
+    fn spans_and_events() {
+        let recorder = Recorder::default();
+        tracing::subscriber::set_global_default(recorder.clone()).unwrap();
+
+        let mut job = Job::new();
+        let echo_task_id = job
+            .push_named("echo", Command::new("echo").arg("traced"))
+            .id();
+        job.push_named("callable", Callable::new(|| 42));
+        let job_id = job.id();
+        job.run_and_return().unwrap();
+        Callable::new(|| 42).run_and_return().unwrap();
+
+        let spans = recorder.spans.lock().unwrap().clone();
+        let events = recorder.events.lock().unwrap().clone();
+        let span = |id: Option<u64>| spans.iter().find(|span| Some(span.id) == id).unwrap();
+        let task = spans
+            .iter()
+            .find(|span| {
+                span.name == "task" && span.field("task_id") == Some(&echo_task_id.to_string())
+            })
+            .unwrap();
+        let job = span(task.parent);
+        assert_eq!(job.name, "job");
+        assert_eq!(job.field("job_id"), Some(job_id.to_string().as_str()));
+        assert_eq!(task.field("job_id"), Some(job_id.to_string().as_str()));
+        let command = spans
+            .iter()
+            .find(|span| span.name == "command" && span.parent == Some(task.id))
+            .unwrap();
+        assert_eq!(command.field("program"), Some("echo"));
+        assert_eq!(command.field("arguments"), Some("traced"));
+
+        let output_line = events
+            .iter()
+            .find(|event| event.parent == Some(command.id) && event.field("line").is_some())
+            .unwrap();
+        assert_eq!(output_line.field("line"), Some("traced"));
+        for id in [command.id, task.id, job.id] {
+            let completion = events
+                .iter()
+                .find(|event| event.parent == Some(id) && event.field("outcome").is_some())
+                .unwrap();
+            assert_eq!(completion.field("outcome"), Some("succeeded"));
+        }
+
+        let callables: Vec<_> = spans
+            .iter()
+            .filter(|span| {
+                span.name == "callable"
+                    && span
+                        .field("handle")
+                        .is_some_and(|handle| handle.contains("spans_and_events::{{closure}}"))
+            })
+            .collect();
+        assert_eq!(callables.len(), 2);
+        let (in_job, alone) = match callables[0].parent {
+            Some(_) => (callables[0], callables[1]),
+            None => (callables[1], callables[0]),
+        };
+        assert_eq!(span(in_job.parent).name, "task");
+        assert_eq!(in_job.field("job_id"), Some(job_id.to_string().as_str()));
+        assert_eq!(alone.parent, None);
+        assert_eq!(alone.field("job_id"), None);
+        assert!(events.iter().any(|event| {
+            event.parent == Some(alone.id) && event.field("outcome") == Some("succeeded")
+        }));
+    }
+}